@@ -21,9 +21,20 @@
 
 //! The functionnality that a Sound or a Music should provide.
 
+use std::time::Duration;
+
 use states::State;
+use fade::FadeCurve;
+use events::PlaybackEvent;
+use distance_model::DistanceModel;
+use effects::{EffectSlot, Filter};
+use error::Error;
 
 /// The functionnality that an Audio Source should provide.
+///
+/// The methods which don't return a Result don't report errors: if the
+/// audio context is not alive, the setters do nothing and the getters return
+/// a default value.
 pub trait AudioController {
     /// Play or resume the Audio Source.
     fn play(&mut self) -> ();
@@ -279,4 +290,278 @@ pub trait AudioController {
      * The current attenuation for the Audio Source in the range [0., 1.].
      */
     fn get_attenuation(&self) -> f32;
+
+    /**
+     * Set the distance model of the Audio Source.
+     *
+     * Needs the AL_EXT_source_distance_model extension. The model is only
+     * used while `listener::set_per_source_distance_model` is enabled,
+     * otherwise all the Audio Sources use the model of
+     * `listener::set_distance_model`.
+     *
+     * # Argument
+     * * `model` - The formula attenuating the Audio Source with its distance
+     *
+     * # Return
+     * False if the OpenAL implementation doesn't support a distance model per
+     * source, true otherwise.
+     */
+    fn set_distance_model(&mut self, model: DistanceModel) -> bool;
+
+    /**
+     * Get the distance model used by the Audio Source.
+     *
+     * # Return
+     * The model of the Audio Source if the Audio Sources use their own model,
+     * the global model otherwise.
+     */
+    fn get_distance_model(&self) -> DistanceModel;
+
+    /**
+     * Send the Audio Source to an effect slot.
+     *
+     * The Audio Source is still played on its direct output, the slot adds
+     * its effect, e.g. a reverberation. The Audio Source keeps the slot alive
+     * until the send is removed.
+     *
+     * # Arguments
+     * * `send` - The auxiliary send used, between 0 and
+     * `effects::get_max_sends()` excluded. Replace the slot previously
+     * connected to this send.
+     * * `slot` - The slot receiving the samples
+     * * `gain` - The volume of the samples sent, between 0. and 1.
+     *
+     * # Return
+     * A Result containing Ok(()) on success, Err(Error) otherwise.
+     */
+    fn set_effect_send(&mut self, send: i32, slot: &EffectSlot, gain: f32) -> Result<(), Error>;
+
+    /**
+     * Stop sending the Audio Source to the effect slot of an auxiliary send.
+     *
+     * # Argument
+     * * `send` - The auxiliary send to disconnect
+     */
+    fn remove_effect_send(&mut self, send: i32) -> ();
+
+    /**
+     * Filter the direct output of the Audio Source.
+     *
+     * The filter changes the sound without changing the volume model, e.g.
+     * a low-pass filter muffles an occluded Audio Source. Nothing happens if
+     * the effects are not supported or the parameters of the filter are out
+     * of range.
+     *
+     * # Argument
+     * * `filter` - The filter applied to the direct output, replace the
+     * previous one
+     */
+    fn set_direct_filter(&mut self, filter: Filter) -> ();
+
+    /// Remove the filter of the direct output of the Audio Source.
+    fn remove_direct_filter(&mut self) -> ();
+
+    /**
+     * Get the filter of the direct output of the Audio Source.
+     *
+     * # Return
+     * The filter applied to the direct output, None if there is no filter
+     * or the effects are not supported.
+     */
+    fn get_direct_filter(&self) -> Option<Filter>;
+
+    /**
+     * Assign the Audio Source to a mixer group.
+     *
+     * The Audio Source is played at its volume multiplied by the volume of
+     * the group, get_volume still returns the volume of the Audio Source
+     * alone. See the `mixer` module.
+     *
+     * # Argument
+     * * `group` - The name of the group, created if needed, or None to remove
+     * the Audio Source from its group
+     */
+    fn set_group(&mut self, group: Option<&str>) -> ();
+
+    /**
+     * Get the mixer group of the Audio Source.
+     *
+     * # Return
+     * The name of the group of the Audio Source, None if it has no group.
+     */
+    fn get_group(&self) -> Option<String>;
+
+    /**
+     * Set the inner angle of the cone of the Audio Source.
+     *
+     * Inside the cone defined by the direction of the Audio Source and this angle,
+     * the Audio Source is not attenuated by its orientation.
+     *
+     * The default inner angle is 360.
+     *
+     * # Argument
+     * * `angle` - The new inner angle in degrees, in the range [0., 360.]
+     */
+    fn set_cone_inner_angle(&mut self, angle: f32) -> ();
+
+    /**
+     * Get the inner angle of the cone of the Audio Source.
+     *
+     * # Return
+     * The inner angle in degrees, in the range [0., 360.]
+     */
+    fn get_cone_inner_angle(&self) -> f32;
+
+    /**
+     * Set the outer angle of the cone of the Audio Source.
+     *
+     * Outside the cone defined by the direction of the Audio Source and this angle,
+     * the volume of the Audio Source is multiplied by the outer volume. Between the
+     * inner and the outer angles, the volume is interpolated.
+     *
+     * The default outer angle is 360.
+     *
+     * # Argument
+     * * `angle` - The new outer angle in degrees, in the range [0., 360.]
+     */
+    fn set_cone_outer_angle(&mut self, angle: f32) -> ();
+
+    /**
+     * Get the outer angle of the cone of the Audio Source.
+     *
+     * # Return
+     * The outer angle in degrees, in the range [0., 360.]
+     */
+    fn get_cone_outer_angle(&self) -> f32;
+
+    /**
+     * Set the volume of the Audio Source outside its cone.
+     *
+     * The default outer volume is 0.
+     *
+     * # Argument
+     * * `volume` - The new outer volume, in the range [0., 1.]
+     */
+    fn set_cone_outer_volume(&mut self, volume: f32) -> ();
+
+    /**
+     * Get the volume of the Audio Source outside its cone.
+     *
+     * # Return
+     * The outer volume, in the range [0., 1.]
+     */
+    fn get_cone_outer_volume(&self) -> f32;
+
+    /**
+     * Set the velocity of the Audio Source.
+     *
+     * The velocity doesn't move the Audio Source, it is only used to compute the
+     * Doppler effect, see `listener::set_doppler_factor`.
+     *
+     * The default velocity is [0., 0., 0.].
+     *
+     * # Argument
+     * * `velocity` - A three dimensional vector of f32 containing the
+     * velocity of the Audio Source [x, y, z], in units per second.
+     */
+    fn set_velocity(&mut self, velocity: [f32, ..3]) -> ();
+
+    /**
+     * Get the velocity of the Audio Source.
+     *
+     * # Return
+     * A three dimensional vector of f32 containing the velocity of the
+     * Audio Source [x, y, z].
+     */
+    fn get_velocity(&self) -> [f32, ..3];
+
+    /**
+     * Set the playback position of the Audio Source.
+     *
+     * # Argument
+     * * `offset` - The new position from the begining of the Audio Source.
+     */
+    fn set_offset(&mut self, offset: Duration) -> ();
+
+    /**
+     * Get the playback position of the Audio Source.
+     *
+     * # Return
+     * The time elapsed since the begining of the Audio Source.
+     */
+    fn get_offset(&self) -> Duration;
+
+    /**
+     * Get the total duration of the Audio Source.
+     *
+     * # Return
+     * The duration of the Audio Source.
+     */
+    fn get_duration(&self) -> Duration;
+
+    /**
+     * Change the volume of the Audio Source progressively.
+     *
+     * The volume is updated by a background task shared by all the Audio
+     * Sources. A new fade replaces the running one.
+     *
+     * # Arguments
+     * * `volume` - The volume at the end of the fade
+     * * `duration` - The duration of the fade
+     * * `curve` - The shape of the volume change
+     *
+     * # Return
+     * A Receiver notified when the fade is done. It is closed without
+     * notification if the fade is replaced or the Audio Source dropped.
+     */
+    fn fade_to(&mut self, volume: f32, duration: Duration, curve: FadeCurve) -> Receiver<()>;
+
+    /**
+     * Change the pitch of the Audio Source progressively.
+     *
+     * The pitch changes linearly, it is updated by a background task shared
+     * by all the Audio Sources. A new ramp replaces the running one.
+     *
+     * # Arguments
+     * * `pitch` - The pitch at the end of the ramp, must be greater than 0.
+     * * `duration` - The duration of the ramp
+     *
+     * # Return
+     * A Receiver notified when the ramp is done. It is closed without
+     * notification if the ramp is replaced or the Audio Source dropped.
+     */
+    fn pitch_ramp(&mut self, pitch: f32, duration: Duration) -> Receiver<()>;
+
+    /**
+     * Get the events of the Audio Source.
+     *
+     * The events are sent when the playback stops, loops back, or when the
+     * streaming buffers of a Music ran out. An Audio Source has only one
+     * channel of events: a new call replaces it, and the Receiver returned
+     * by the previous call is closed.
+     *
+     * # Return
+     * The Receiver of the PlaybackEvents of the Audio Source.
+     */
+    fn events(&mut self) -> Receiver<PlaybackEvent>;
+
+    /**
+     * Block until the Audio Source is not playing anymore.
+     *
+     * Return immediately if the Audio Source is paused or stopped.
+     */
+    fn wait_until_stopped(&self) -> ();
+
+    /**
+     * Block until the Audio Source is not playing anymore, or the timeout
+     * expires.
+     *
+     * # Argument
+     * * `timeout` - The maximum duration of the wait
+     *
+     * # Return
+     * True if the Audio Source is not playing anymore, false if the timeout
+     * expired.
+     */
+    fn wait_timeout(&self, timeout: Duration) -> bool;
 }
@@ -0,0 +1,126 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2014 Jeremy Letang (letang.jeremy@gmail.com)
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+//! The audio context shared by all the __ears__ objects.
+
+use std::sync::Arc;
+use libc::c_void;
+
+use internal::OpenAlData;
+use openal::{ffi, al};
+
+/**
+ * A handle to the audio context.
+ *
+ * The audio context owns the OpenAL device and context. It is shared by the
+ * whole program: every Sound, SoundData, Music and Recorder hold a handle to
+ * it, and it can be cloned and sent to other tasks.
+ * The context is destroyed when the last handle is dropped, so keep the
+ * AudioContext returned by `ears::init()` alive to keep the listener
+ * settings between the lifetime of your sounds.
+ *
+ * # Example
+ * ```Rust
+ * extern crate ears;
+ *
+ * fn main() -> () {
+ *     // The context lives until the end of main.
+ *     let context = ears::init().unwrap();
+ *
+ *     let ctxt = context.clone();
+ *     spawn(proc() {
+ *         // Use ears in another task, the context is shared.
+ *         let _ = ctxt;
+ *     });
+ * }
+ * ```
+ */
+#[deriving(Clone)]
+pub struct AudioContext {
+    al_data: Arc<OpenAlData>
+}
+
+impl AudioContext {
+    /**
+     * Get the name of the device used by the context.
+     *
+     * # Return
+     * The name of the output device, or None if OpenAL doesn't provide it.
+     */
+    pub fn get_device_name(&self) -> Option<String> {
+        let param = if al::alcIsExtensionPresent(self.al_data.al_device,
+                                                 "ALC_ENUMERATE_ALL_EXT") {
+            ffi::ALC_ALL_DEVICES_SPECIFIER
+        } else {
+            ffi::ALC_DEVICE_SPECIFIER
+        };
+        al::alcGetString(self.al_data.al_device, param)
+    }
+
+    /**
+     * Check if the context use a loopback device.
+     *
+     * # Return
+     * true if the context was created by `ears::init_loopback()`, false
+     * otherwise.
+     */
+    pub fn is_loopback(&self) -> bool {
+        self.al_data.render_samples.is_some()
+    }
+
+    /**
+     * Render the mix of the playing sources.
+     *
+     * Only a loopback context can be rendered, the samples are 16 bits
+     * interleaved stereo at the samplerate given to `ears::init_loopback()`.
+     * The playback of the sources advances by the number of rendered frames.
+     *
+     * # Argument
+     * * `samples` - The buffer to fill, its length should be a multiple of 2
+     *
+     * # Return
+     * true if the samples were rendered, false if the context doesn't use a
+     * loopback device.
+     */
+    pub fn render(&self, samples: &mut [i16]) -> bool {
+        match self.al_data.render_samples {
+            Some(render_samples) => {
+                render_samples(self.al_data.al_device,
+                               samples.as_mut_ptr() as *mut c_void,
+                               (samples.len() / 2) as i32);
+                true
+            },
+            None                 => false
+        }
+    }
+}
+
+#[doc(hidden)]
+pub fn new(al_data: Arc<OpenAlData>) -> AudioContext {
+    AudioContext {
+        al_data: al_data
+    }
+}
+
+#[doc(hidden)]
+pub fn get<'r>(ctxt: &'r AudioContext) -> &'r OpenAlData {
+    &*ctxt.al_data
+}
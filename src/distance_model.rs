@@ -0,0 +1,157 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2014 Jeremy Letang (letang.jeremy@gmail.com)
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+//! The attenuation of the sources with their distance to the listener.
+
+use openal::{ffi, al};
+
+/// The name of the extension allowing a distance model per source.
+const SOURCE_DISTANCE_MODEL_EXT: &'static str = "AL_EXT_source_distance_model";
+
+/**
+ * The formula attenuating a source with its distance to the listener.
+ *
+ * The formulas use the reference distance, the maximum distance and the
+ * attenuation (rolloff factor) of each source. The clamped models don't
+ * attenuate the sources closer than their reference distance and don't
+ * attenuate them any further beyond their maximum distance.
+ */
+#[deriving(Clone, PartialEq, Show, Copy)]
+pub enum DistanceModel {
+    /// The sources are not attenuated by the distance
+    None,
+    /// The volume is inversely proportional to the distance
+    Inverse,
+    /// Inverse, clamped between the reference and the maximum distances
+    InverseClamped,
+    /// The volume decreases linearly until the maximum distance
+    Linear,
+    /// Linear, clamped between the reference and the maximum distances
+    LinearClamped,
+    /// The volume decreases exponentially with the distance
+    Exponent,
+    /// Exponent, clamped between the reference and the maximum distances
+    ExponentClamped
+}
+
+impl DistanceModel {
+    /// Get the OpenAL identifier of the model.
+    #[doc(hidden)]
+    pub fn to_al(&self) -> i32 {
+        match *self {
+            DistanceModel::None            => ffi::AL_NONE,
+            DistanceModel::Inverse         => ffi::AL_INVERSE_DISTANCE,
+            DistanceModel::InverseClamped  => ffi::AL_INVERSE_DISTANCE_CLAMPED,
+            DistanceModel::Linear          => ffi::AL_LINEAR_DISTANCE,
+            DistanceModel::LinearClamped   => ffi::AL_LINEAR_DISTANCE_CLAMPED,
+            DistanceModel::Exponent        => ffi::AL_EXPONENT_DISTANCE,
+            DistanceModel::ExponentClamped => ffi::AL_EXPONENT_DISTANCE_CLAMPED
+        }
+    }
+
+    /// Get the model of an OpenAL identifier.
+    #[doc(hidden)]
+    pub fn from_al(model: i32) -> Option<DistanceModel> {
+        match model {
+            ffi::AL_NONE                      => Some(DistanceModel::None),
+            ffi::AL_INVERSE_DISTANCE          => Some(DistanceModel::Inverse),
+            ffi::AL_INVERSE_DISTANCE_CLAMPED  => Some(DistanceModel::InverseClamped),
+            ffi::AL_LINEAR_DISTANCE           => Some(DistanceModel::Linear),
+            ffi::AL_LINEAR_DISTANCE_CLAMPED   => Some(DistanceModel::LinearClamped),
+            ffi::AL_EXPONENT_DISTANCE         => Some(DistanceModel::Exponent),
+            ffi::AL_EXPONENT_DISTANCE_CLAMPED => Some(DistanceModel::ExponentClamped),
+            _                                 => None
+        }
+    }
+}
+
+/// Check if the sources can have their own distance model.
+#[doc(hidden)]
+pub fn is_source_model_supported() -> bool {
+    al::alIsExtensionPresent(SOURCE_DISTANCE_MODEL_EXT)
+}
+
+/// Check if the sources use their own distance model.
+#[doc(hidden)]
+pub fn is_source_model_enabled() -> bool {
+    is_source_model_supported() && al::alIsEnabled(ffi::AL_SOURCE_DISTANCE_MODEL)
+}
+
+/// Make the sources use their own distance model, or the global one.
+///
+/// Return false if the sources can't have their own distance model.
+#[doc(hidden)]
+pub fn enable_source_model(enabled: bool) -> bool {
+    if !is_source_model_supported() {
+        return false;
+    }
+    if enabled {
+        al::alEnable(ffi::AL_SOURCE_DISTANCE_MODEL);
+    } else {
+        al::alDisable(ffi::AL_SOURCE_DISTANCE_MODEL);
+    }
+    true
+}
+
+/// Set the distance model of a source, used while the sources use their own
+/// distance model.
+///
+/// Return false if the sources can't have their own distance model.
+#[doc(hidden)]
+pub fn set_source_model(source: u32, model: DistanceModel) -> bool {
+    if !is_source_model_supported() {
+        return false;
+    }
+    al::alSourcei(source, ffi::AL_DISTANCE_MODEL, model.to_al());
+    true
+}
+
+/// Get the distance model used by a source.
+#[doc(hidden)]
+pub fn get_source_model(source: u32) -> DistanceModel {
+    let model = if is_source_model_enabled() {
+        let mut model = 0;
+        al::alGetSourcei(source, ffi::AL_DISTANCE_MODEL, &mut model);
+        model
+    } else {
+        al::alGetInteger(ffi::AL_DISTANCE_MODEL)
+    };
+    DistanceModel::from_al(model).unwrap_or(DistanceModel::InverseClamped)
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(non_snake_case)]
+
+    use distance_model::DistanceModel;
+
+    #[test]
+    fn distance_model_al_OK() -> () {
+        let models = [DistanceModel::None, DistanceModel::Inverse,
+                      DistanceModel::InverseClamped, DistanceModel::Linear,
+                      DistanceModel::LinearClamped, DistanceModel::Exponent,
+                      DistanceModel::ExponentClamped];
+        for model in models.iter() {
+            assert_eq!(DistanceModel::from_al(model.to_al()), Some(*model));
+        }
+        assert_eq!(DistanceModel::from_al(-1), None);
+    }
+}
@@ -43,7 +43,7 @@ fn main() {
 	snd.play();
 
 	// Wait until the end of the sound
-	while snd.is_playing() {}
+	snd.wait_until_stopped();
 }
 ```
 
@@ -99,17 +99,28 @@ use ears::Music;
 #![feature(unsafe_destructor)]
 
 extern crate libc;
+extern crate time;
 
 // Reexport public API
-pub use einit::{init, init_in};
+pub use einit::{init, init_with_device, init_loopback, devices, default_device};
+pub use einit::{init_in, init_in_with, capture_devices, default_capture_device};
+pub use context::AudioContext;
 pub use music::Music;
+pub use playlist::{Playlist, PlaylistEvent, Repeat};
+pub use fade::{FadeCurve, crossfade};
+pub use events::PlaybackEvent;
+pub use distance_model::DistanceModel;
 pub use sound::Sound;
 pub use states::State;
 pub use sound_data::SoundData;
 pub use audio_controller::AudioController;
 pub use audio_tags::{AudioTags, Tags};
 pub use recorder::Recorder;
-pub use record_context::RecordContext;
+pub use record_context::{RecordContext, CaptureFormat};
+pub use error::{Error, OpenAlError};
+pub use sample_source::SampleSource;
+pub use sndfile::FormatType;
+pub use input_level::{InputLevel, VoiceDetection, VoiceActivityDetector, VoiceEvent};
 
 
 // Hidden internal bindings
@@ -122,11 +133,21 @@ mod sndfile;
 #[path = "init.rs"]
 mod einit;
 pub mod listener;
+pub mod effects;
+pub mod mixer;
 mod sound;
 mod music;
+mod playlist;
+mod fade;
+mod events;
+mod distance_model;
 mod sound_data;
 mod states;
 mod audio_controller;
 mod audio_tags;
 mod recorder;
 mod record_context;
+mod error;
+mod sample_source;
+mod context;
+mod input_level;
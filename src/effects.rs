@@ -0,0 +1,695 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2014 Jeremy Letang (letang.jeremy@gmail.com)
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+/*!
+Environmental effects, e.g. the reverberation of a room.
+
+The effects use the EFX extension of OpenAL. An effect is loaded in an
+EffectSlot, then the Sounds and the Musics send their samples to the slot
+through one of their auxiliary sends, in addition to their direct output.
+
+# Example
+```Rust
+extern crate ears;
+use ears::{Sound, AudioController};
+use ears::effects::{EffectSlot, Effect, Reverb};
+
+fn main() -> () {
+    let hall = EffectSlot::new(Effect::Reverb(Reverb::concert_hall())).unwrap();
+
+    let mut snd = Sound::new("path/to/my/sound.ogg").unwrap();
+    // Send the sound to the reverb on its first send, at half volume
+    snd.set_effect_send(0, &hall, 0.5).unwrap();
+    snd.play();
+}
+```
+*/
+
+use std::sync::Arc;
+use std::default::Default;
+
+use internal::OpenAlData;
+use context;
+use context::AudioContext;
+use error::Error;
+use openal::{ffi, al};
+
+/**
+ * Check if the effects are supported.
+ *
+ * # Return
+ * True if the OpenAL implementation supports the EFX extension, false
+ * otherwise.
+ */
+pub fn is_supported() -> bool {
+    get_efx().is_ok()
+}
+
+/**
+ * Get the number of auxiliary sends of each source.
+ *
+ * # Return
+ * The number of effect slots a Sound or a Music can send to at the same
+ * time, 0 if the effects are not supported.
+ */
+pub fn get_max_sends() -> i32 {
+    let (context, _) = match get_efx() {
+        Ok(efx) => efx,
+        Err(_)  => return 0
+    };
+    let mut sends = 0;
+    unsafe {
+        ffi::alcGetIntegerv(context::get(&context).al_device,
+                            ffi::ALC_MAX_AUXILIARY_SENDS, 1, &mut sends);
+    }
+    sends
+}
+
+/// Get the EFX functions of the shared context.
+fn get_efx() -> Result<(AudioContext, ffi::Efx), Error> {
+    let context = try!(OpenAlData::check_al_context());
+    let efx = context::get(&context).efx;
+    match efx {
+        Some(efx) => Ok((context, efx)),
+        None      => Err(Error::EfxUnsupported)
+    }
+}
+
+/// A reverberation, parameters of the EFX standard reverb.
+#[deriving(Clone, PartialEq, Show, Copy)]
+pub struct Reverb {
+    /// Density of the reflections, in the range [0., 1.]
+    pub density: f32,
+    /// Echo density of the late reverberation, in the range [0., 1.]
+    pub diffusion: f32,
+    /// Volume of the reverberation, in the range [0., 1.]
+    pub gain: f32,
+    /// Volume of the high frequencies, in the range [0., 1.]
+    pub gain_hf: f32,
+    /// Duration of the reverberation in seconds, in the range [0.1, 20.]
+    pub decay_time: f32,
+    /// Ratio of the decay time of the high frequencies, in the range
+    /// [0.1, 2.]
+    pub decay_hf_ratio: f32,
+    /// Volume of the early reflections, in the range [0., 3.16]
+    pub reflections_gain: f32,
+    /// Delay of the early reflections in seconds, in the range [0., 0.3]
+    pub reflections_delay: f32,
+    /// Volume of the late reverberation, in the range [0., 10.]
+    pub late_reverb_gain: f32,
+    /// Delay of the late reverberation after the early reflections in
+    /// seconds, in the range [0., 0.1]
+    pub late_reverb_delay: f32,
+    /// Absorption of the high frequencies by the air, in the range
+    /// [0.892, 1.]
+    pub air_absorption_gain_hf: f32,
+    /// Attenuation of the reverberation with the distance, in the range
+    /// [0., 10.]
+    pub room_rolloff_factor: f32,
+    /// Limit the decay of the high frequencies by the air absorption
+    pub decay_hf_limit: bool
+}
+
+impl Reverb {
+    /// Build a preset from the values of the EFX presets.
+    fn preset(density: f32, diffusion: f32, gain: f32, gain_hf: f32,
+              decay_time: f32, decay_hf_ratio: f32,
+              reflections_gain: f32, reflections_delay: f32,
+              late_reverb_gain: f32, late_reverb_delay: f32,
+              decay_hf_limit: bool) -> Reverb {
+        Reverb {
+            density: density,
+            diffusion: diffusion,
+            gain: gain,
+            gain_hf: gain_hf,
+            decay_time: decay_time,
+            decay_hf_ratio: decay_hf_ratio,
+            reflections_gain: reflections_gain,
+            reflections_delay: reflections_delay,
+            late_reverb_gain: late_reverb_gain,
+            late_reverb_delay: late_reverb_delay,
+            air_absorption_gain_hf: 0.9943,
+            room_rolloff_factor: 0.,
+            decay_hf_limit: decay_hf_limit
+        }
+    }
+
+    /// A generic environment, the default reverb.
+    pub fn generic() -> Reverb {
+        Reverb::preset(1., 1., 0.3162, 0.8913, 1.49, 0.83, 0.05, 0.007, 1.2589, 0.011, true)
+    }
+
+    /// A small room.
+    pub fn room() -> Reverb {
+        Reverb::preset(0.4287, 1., 0.3162, 0.5929, 0.4, 0.83, 0.1503, 0.002, 1.0629, 0.003, true)
+    }
+
+    /// A tiled bathroom.
+    pub fn bathroom() -> Reverb {
+        Reverb::preset(0.1715, 1., 0.3162, 0.2512, 1.49, 0.54, 0.6531, 0.007, 3.2734, 0.011, true)
+    }
+
+    /// A room with stone walls.
+    pub fn stone_room() -> Reverb {
+        Reverb::preset(1., 1., 0.3162, 0.7079, 2.31, 0.64, 0.4411, 0.012, 1.1003, 0.017, true)
+    }
+
+    /// A long corridor.
+    pub fn hallway() -> Reverb {
+        Reverb::preset(0.3645, 1., 0.3162, 0.7079, 1.49, 0.59, 0.2458, 0.007, 1.6615, 0.011, true)
+    }
+
+    /// A concert hall.
+    pub fn concert_hall() -> Reverb {
+        Reverb::preset(1., 1., 0.3162, 0.5623, 3.92, 0.7, 0.2427, 0.02, 0.9977, 0.029, true)
+    }
+
+    /// A cave.
+    pub fn cave() -> Reverb {
+        Reverb::preset(1., 1., 0.3162, 1., 2.91, 1.3, 0.5, 0.015, 0.7063, 0.022, false)
+    }
+
+    /// A sport arena.
+    pub fn arena() -> Reverb {
+        Reverb::preset(1., 1., 0.3162, 0.4477, 7.24, 0.33, 0.2612, 0.02, 1.0186, 0.03, true)
+    }
+
+    /// A hangar.
+    pub fn hangar() -> Reverb {
+        Reverb::preset(1., 1., 0.3162, 0.3162, 10.05, 0.23, 0.5, 0.02, 1.256, 0.03, true)
+    }
+
+    /// Under water.
+    pub fn underwater() -> Reverb {
+        Reverb::preset(0.3645, 1., 0.3162, 0.01, 1.49, 0.1, 0.5963, 0.007, 7.0795, 0.011, true)
+    }
+}
+
+/// An echo, parameters of the EFX echo.
+#[deriving(Clone, PartialEq, Show, Copy)]
+pub struct Echo {
+    /// Delay of the first echo in seconds, in the range [0., 0.207]
+    pub delay: f32,
+    /// Delay between the first and the second echo in seconds, in the range
+    /// [0., 0.404]
+    pub lr_delay: f32,
+    /// Attenuation of the high frequencies of the echoes, in the range
+    /// [0., 0.99]
+    pub damping: f32,
+    /// Volume of each echo relative to the previous one, in the range
+    /// [0., 1.]
+    pub feedback: f32,
+    /// Stereo spread of the echoes, from -1. (left then right) to 1.
+    pub spread: f32
+}
+
+/// The shape of the modulation of a chorus.
+#[deriving(Clone, PartialEq, Show, Copy)]
+pub enum ChorusWaveform {
+    /// The delay follows a sine
+    Sine,
+    /// The delay changes linearly
+    Triangle
+}
+
+/// A chorus, parameters of the EFX chorus.
+#[deriving(Clone, PartialEq, Show, Copy)]
+pub struct Chorus {
+    /// The shape of the modulation of the delay
+    pub waveform: ChorusWaveform,
+    /// Phase difference between the left and right modulations in degrees,
+    /// in the range [-180, 180]
+    pub phase: i32,
+    /// Frequency of the modulation in Hz, in the range [0., 10.]
+    pub rate: f32,
+    /// Amount of the modulation, in the range [0., 1.]
+    pub depth: f32,
+    /// Amount of the output fed back, in the range [-1., 1.]
+    pub feedback: f32,
+    /// Average delay in seconds, in the range [0., 0.016]
+    pub delay: f32
+}
+
+/// A distortion, parameters of the EFX distortion.
+#[deriving(Clone, PartialEq, Show, Copy)]
+pub struct Distortion {
+    /// Shape of the distortion, in the range [0., 1.]
+    pub edge: f32,
+    /// Volume of the distorted signal, in the range [0.01, 1.]
+    pub gain: f32,
+    /// Cutoff of the low-pass filter applied before the distortion in Hz,
+    /// in the range [80., 24000.]
+    pub lowpass_cutoff: f32,
+    /// Center of the distorted frequency band in Hz, in the range
+    /// [80., 24000.]
+    pub eq_center: f32,
+    /// Width of the distorted frequency band in Hz, in the range
+    /// [80., 24000.]
+    pub eq_bandwidth: f32
+}
+
+/// A four band equalizer, parameters of the EFX equalizer.
+///
+/// The gains are in the range [0.126, 7.943], 1. leaves the band unchanged.
+#[deriving(Clone, PartialEq, Show, Copy)]
+pub struct Equalizer {
+    pub low_gain: f32,
+    /// In Hz, in the range [50., 800.]
+    pub low_cutoff: f32,
+    pub mid1_gain: f32,
+    /// In Hz, in the range [200., 3000.]
+    pub mid1_center: f32,
+    /// In octaves, in the range [0.01, 1.]
+    pub mid1_width: f32,
+    pub mid2_gain: f32,
+    /// In Hz, in the range [1000., 8000.]
+    pub mid2_center: f32,
+    /// In octaves, in the range [0.01, 1.]
+    pub mid2_width: f32,
+    pub high_gain: f32,
+    /// In Hz, in the range [4000., 16000.]
+    pub high_cutoff: f32
+}
+
+impl Default for Reverb {
+    fn default() -> Reverb {
+        Reverb::generic()
+    }
+}
+
+impl Default for Echo {
+    fn default() -> Echo {
+        Echo { delay: 0.1, lr_delay: 0.1, damping: 0.5, feedback: 0.5, spread: -1. }
+    }
+}
+
+impl Default for Chorus {
+    fn default() -> Chorus {
+        Chorus {
+            waveform: ChorusWaveform::Triangle,
+            phase: 90,
+            rate: 1.1,
+            depth: 0.1,
+            feedback: 0.25,
+            delay: 0.016
+        }
+    }
+}
+
+impl Default for Distortion {
+    fn default() -> Distortion {
+        Distortion {
+            edge: 0.2,
+            gain: 0.05,
+            lowpass_cutoff: 8000.,
+            eq_center: 3600.,
+            eq_bandwidth: 3600.
+        }
+    }
+}
+
+impl Default for Equalizer {
+    fn default() -> Equalizer {
+        Equalizer {
+            low_gain: 1.,
+            low_cutoff: 200.,
+            mid1_gain: 1.,
+            mid1_center: 500.,
+            mid1_width: 1.,
+            mid2_gain: 1.,
+            mid2_center: 3000.,
+            mid2_width: 1.,
+            high_gain: 1.,
+            high_cutoff: 6000.
+        }
+    }
+}
+
+/// An effect which can be loaded in an EffectSlot.
+#[deriving(Clone, PartialEq, Show, Copy)]
+pub enum Effect {
+    Reverb(Reverb),
+    Echo(Echo),
+    Chorus(Chorus),
+    Distortion(Distortion),
+    Equalizer(Equalizer)
+}
+
+impl Effect {
+    /// Set the type and the parameters of an OpenAL effect.
+    fn apply(&self, efx: &ffi::Efx, effect: u32) -> () {
+        match *self {
+            Effect::Reverb(ref r) => {
+                (efx.alEffecti)(effect, ffi::AL_EFFECT_TYPE, ffi::AL_EFFECT_REVERB);
+                (efx.alEffectf)(effect, ffi::AL_REVERB_DENSITY, r.density);
+                (efx.alEffectf)(effect, ffi::AL_REVERB_DIFFUSION, r.diffusion);
+                (efx.alEffectf)(effect, ffi::AL_REVERB_GAIN, r.gain);
+                (efx.alEffectf)(effect, ffi::AL_REVERB_GAINHF, r.gain_hf);
+                (efx.alEffectf)(effect, ffi::AL_REVERB_DECAY_TIME, r.decay_time);
+                (efx.alEffectf)(effect, ffi::AL_REVERB_DECAY_HFRATIO, r.decay_hf_ratio);
+                (efx.alEffectf)(effect, ffi::AL_REVERB_REFLECTIONS_GAIN, r.reflections_gain);
+                (efx.alEffectf)(effect, ffi::AL_REVERB_REFLECTIONS_DELAY, r.reflections_delay);
+                (efx.alEffectf)(effect, ffi::AL_REVERB_LATE_REVERB_GAIN, r.late_reverb_gain);
+                (efx.alEffectf)(effect, ffi::AL_REVERB_LATE_REVERB_DELAY, r.late_reverb_delay);
+                (efx.alEffectf)(effect, ffi::AL_REVERB_AIR_ABSORPTION_GAINHF,
+                                r.air_absorption_gain_hf);
+                (efx.alEffectf)(effect, ffi::AL_REVERB_ROOM_ROLLOFF_FACTOR,
+                                r.room_rolloff_factor);
+                (efx.alEffecti)(effect, ffi::AL_REVERB_DECAY_HFLIMIT, r.decay_hf_limit as i32);
+            },
+            Effect::Echo(ref e) => {
+                (efx.alEffecti)(effect, ffi::AL_EFFECT_TYPE, ffi::AL_EFFECT_ECHO);
+                (efx.alEffectf)(effect, ffi::AL_ECHO_DELAY, e.delay);
+                (efx.alEffectf)(effect, ffi::AL_ECHO_LRDELAY, e.lr_delay);
+                (efx.alEffectf)(effect, ffi::AL_ECHO_DAMPING, e.damping);
+                (efx.alEffectf)(effect, ffi::AL_ECHO_FEEDBACK, e.feedback);
+                (efx.alEffectf)(effect, ffi::AL_ECHO_SPREAD, e.spread);
+            },
+            Effect::Chorus(ref c) => {
+                let waveform = match c.waveform {
+                    ChorusWaveform::Sine     => 0,
+                    ChorusWaveform::Triangle => 1
+                };
+                (efx.alEffecti)(effect, ffi::AL_EFFECT_TYPE, ffi::AL_EFFECT_CHORUS);
+                (efx.alEffecti)(effect, ffi::AL_CHORUS_WAVEFORM, waveform);
+                (efx.alEffecti)(effect, ffi::AL_CHORUS_PHASE, c.phase);
+                (efx.alEffectf)(effect, ffi::AL_CHORUS_RATE, c.rate);
+                (efx.alEffectf)(effect, ffi::AL_CHORUS_DEPTH, c.depth);
+                (efx.alEffectf)(effect, ffi::AL_CHORUS_FEEDBACK, c.feedback);
+                (efx.alEffectf)(effect, ffi::AL_CHORUS_DELAY, c.delay);
+            },
+            Effect::Distortion(ref d) => {
+                (efx.alEffecti)(effect, ffi::AL_EFFECT_TYPE, ffi::AL_EFFECT_DISTORTION);
+                (efx.alEffectf)(effect, ffi::AL_DISTORTION_EDGE, d.edge);
+                (efx.alEffectf)(effect, ffi::AL_DISTORTION_GAIN, d.gain);
+                (efx.alEffectf)(effect, ffi::AL_DISTORTION_LOWPASS_CUTOFF, d.lowpass_cutoff);
+                (efx.alEffectf)(effect, ffi::AL_DISTORTION_EQCENTER, d.eq_center);
+                (efx.alEffectf)(effect, ffi::AL_DISTORTION_EQBANDWIDTH, d.eq_bandwidth);
+            },
+            Effect::Equalizer(ref q) => {
+                (efx.alEffecti)(effect, ffi::AL_EFFECT_TYPE, ffi::AL_EFFECT_EQUALIZER);
+                (efx.alEffectf)(effect, ffi::AL_EQUALIZER_LOW_GAIN, q.low_gain);
+                (efx.alEffectf)(effect, ffi::AL_EQUALIZER_LOW_CUTOFF, q.low_cutoff);
+                (efx.alEffectf)(effect, ffi::AL_EQUALIZER_MID1_GAIN, q.mid1_gain);
+                (efx.alEffectf)(effect, ffi::AL_EQUALIZER_MID1_CENTER, q.mid1_center);
+                (efx.alEffectf)(effect, ffi::AL_EQUALIZER_MID1_WIDTH, q.mid1_width);
+                (efx.alEffectf)(effect, ffi::AL_EQUALIZER_MID2_GAIN, q.mid2_gain);
+                (efx.alEffectf)(effect, ffi::AL_EQUALIZER_MID2_CENTER, q.mid2_center);
+                (efx.alEffectf)(effect, ffi::AL_EQUALIZER_MID2_WIDTH, q.mid2_width);
+                (efx.alEffectf)(effect, ffi::AL_EQUALIZER_HIGH_GAIN, q.high_gain);
+                (efx.alEffectf)(effect, ffi::AL_EQUALIZER_HIGH_CUTOFF, q.high_cutoff);
+            }
+        }
+    }
+}
+
+/// The OpenAL effect slot, deleted when the last EffectSlot is dropped.
+struct SlotData {
+    /// Keep the context alive while the slot exists
+    context: AudioContext,
+    efx: ffi::Efx,
+    al_slot: u32
+}
+
+impl Drop for SlotData {
+    fn drop(&mut self) -> () {
+        (self.efx.alDeleteAuxiliaryEffectSlots)(1, &self.al_slot);
+    }
+}
+
+/**
+ * A slot processing the samples sent by the sources with an effect.
+ *
+ * An EffectSlot is a handle, its clones use the same slot. The Sounds and
+ * the Musics sending to the slot keep it alive.
+ */
+#[deriving(Clone)]
+pub struct EffectSlot {
+    data: Arc<SlotData>
+}
+
+impl EffectSlot {
+    /**
+     * Create a new EffectSlot.
+     *
+     * # Argument
+     * * `effect` - The effect applied by the slot
+     *
+     * # Return
+     * A Result containing Ok(EffectSlot) on success, Err(Error) otherwise,
+     * Error::EfxUnsupported if the effects are not supported.
+     */
+    pub fn new(effect: Effect) -> Result<EffectSlot, Error> {
+        let (context, efx) = try!(get_efx());
+
+        let mut al_slot = 0;
+        (efx.alGenAuxiliaryEffectSlots)(1, &mut al_slot);
+        match al::openal_has_error() {
+            Some(err) => return Err(err),
+            None      => {}
+        };
+
+        let mut slot = EffectSlot {
+            data: Arc::new(SlotData {
+                context: context,
+                efx: efx,
+                al_slot: al_slot
+            })
+        };
+        try!(slot.set_effect(effect));
+        Ok(slot)
+    }
+
+    /**
+     * Replace the effect of the slot.
+     *
+     * # Argument
+     * * `effect` - The new effect applied by the slot
+     *
+     * # Return
+     * A Result containing Ok(()) on success, Err(Error) if the OpenAL
+     * implementation doesn't support the effect.
+     */
+    pub fn set_effect(&mut self, effect: Effect) -> Result<(), Error> {
+        let efx = self.data.efx;
+
+        let mut al_effect = 0;
+        (efx.alGenEffects)(1, &mut al_effect);
+        match al::openal_has_error() {
+            Some(err) => return Err(err),
+            None      => {}
+        };
+        effect.apply(&efx, al_effect);
+        // The slot keeps a copy of the effect
+        (efx.alAuxiliaryEffectSloti)(self.data.al_slot,
+                                     ffi::AL_EFFECTSLOT_EFFECT,
+                                     al_effect as i32);
+        let error = al::openal_has_error();
+        (efx.alDeleteEffects)(1, &al_effect);
+
+        match error {
+            Some(err) => Err(err),
+            None      => Ok(())
+        }
+    }
+
+    /**
+     * Set the volume of the output of the slot.
+     *
+     * # Argument
+     * * `volume` - The volume of the effect, between 0. and 1.
+     */
+    pub fn set_volume(&mut self, volume: f32) -> () {
+        (self.data.efx.alAuxiliaryEffectSlotf)(self.data.al_slot,
+                                               ffi::AL_EFFECTSLOT_GAIN,
+                                               volume);
+    }
+
+    /**
+     * Get the volume of the output of the slot.
+     *
+     * # Return
+     * The volume of the effect, between 0. and 1.
+     */
+    pub fn get_volume(&self) -> f32 {
+        let mut volume = 0.;
+        (self.data.efx.alGetAuxiliaryEffectSlotf)(self.data.al_slot,
+                                                  ffi::AL_EFFECTSLOT_GAIN,
+                                                  &mut volume);
+        volume
+    }
+}
+
+/**
+ * A filter applied to the direct output of a source.
+ *
+ * The gains are in the range [0., 1.], e.g. a low-pass filter with a low
+ * gain_hf muffles a Sound heard through a wall.
+ */
+#[deriving(Clone, PartialEq, Show, Copy)]
+pub enum Filter {
+    /// Attenuate the high frequencies
+    LowPass { gain: f32, gain_hf: f32 },
+    /// Attenuate the low frequencies
+    HighPass { gain: f32, gain_lf: f32 },
+    /// Attenuate the low and the high frequencies
+    BandPass { gain: f32, gain_lf: f32, gain_hf: f32 }
+}
+
+impl Filter {
+    /// Create an OpenAL filter with the parameters of the Filter.
+    fn create(&self, efx: &ffi::Efx) -> Result<u32, Error> {
+        match *self {
+            Filter::LowPass { gain, gain_hf } =>
+                create_filter(efx, ffi::AL_FILTER_LOWPASS,
+                              &[(ffi::AL_LOWPASS_GAIN, gain),
+                                (ffi::AL_LOWPASS_GAINHF, gain_hf)]),
+            Filter::HighPass { gain, gain_lf } =>
+                create_filter(efx, ffi::AL_FILTER_HIGHPASS,
+                              &[(ffi::AL_HIGHPASS_GAIN, gain),
+                                (ffi::AL_HIGHPASS_GAINLF, gain_lf)]),
+            Filter::BandPass { gain, gain_lf, gain_hf } =>
+                create_filter(efx, ffi::AL_FILTER_BANDPASS,
+                              &[(ffi::AL_BANDPASS_GAIN, gain),
+                                (ffi::AL_BANDPASS_GAINLF, gain_lf),
+                                (ffi::AL_BANDPASS_GAINHF, gain_hf)])
+        }
+    }
+}
+
+/// Create a filter with the given parameters.
+///
+/// The filters are copied where they are used, so the caller deletes it
+/// right after use.
+fn create_filter(efx: &ffi::Efx, filter_type: i32, params: &[(i32, f32)]) -> Result<u32, Error> {
+    let mut filter = 0;
+    (efx.alGenFilters)(1, &mut filter);
+    match al::openal_has_error() {
+        Some(err) => return Err(err),
+        None      => {}
+    };
+    (efx.alFilteri)(filter, ffi::AL_FILTER_TYPE, filter_type);
+    for &(param, value) in params.iter() {
+        (efx.alFilterf)(filter, param, value);
+    }
+    // An invalid parameter must not be attached to a source
+    match al::openal_has_error() {
+        Some(err) => {
+            (efx.alDeleteFilters)(1, &filter);
+            Err(err)
+        },
+        None      => Ok(filter)
+    }
+}
+
+/// Send the samples of a source to an effect slot.
+#[doc(hidden)]
+pub fn connect_send(source: u32, send: i32, slot: &EffectSlot, gain: f32) -> Result<(), Error> {
+    let efx = slot.data.efx;
+
+    // The gain of a send is set by a filter
+    let filter = if gain == 1. {
+        ffi::AL_FILTER_NULL as u32
+    } else {
+        try!(create_filter(&efx, ffi::AL_FILTER_LOWPASS,
+                           &[(ffi::AL_LOWPASS_GAIN, gain), (ffi::AL_LOWPASS_GAINHF, 1.)]))
+    };
+    al::alSource3i(source, ffi::AL_AUXILIARY_SEND_FILTER,
+                   slot.data.al_slot as i32, send, filter as i32);
+    let error = al::openal_has_error();
+    if filter != ffi::AL_FILTER_NULL as u32 {
+        (efx.alDeleteFilters)(1, &filter);
+    }
+
+    match error {
+        Some(err) => Err(err),
+        None      => Ok(())
+    }
+}
+
+/// Set or remove the filter of the direct output of a source.
+///
+/// Return false if the effects are not supported or the filter can't be
+/// applied.
+#[doc(hidden)]
+pub fn set_direct_filter(source: u32, filter: Option<Filter>) -> bool {
+    let (_, efx) = match get_efx() {
+        Ok(efx) => efx,
+        Err(_)  => return false
+    };
+
+    let al_filter = match filter {
+        Some(filter) => match filter.create(&efx) {
+            Ok(al_filter) => al_filter,
+            Err(_)        => return false
+        },
+        None         => ffi::AL_FILTER_NULL as u32
+    };
+    al::alSourcei(source, ffi::AL_DIRECT_FILTER, al_filter as i32);
+    let error = al::openal_has_error();
+    if al_filter != ffi::AL_FILTER_NULL as u32 {
+        (efx.alDeleteFilters)(1, &al_filter);
+    }
+    error.is_none()
+}
+
+/// Stop sending the samples of a source to an effect slot.
+#[doc(hidden)]
+pub fn disconnect_send(source: u32, send: i32) -> () {
+    if is_supported() {
+        al::alSource3i(source, ffi::AL_AUXILIARY_SEND_FILTER,
+                       ffi::AL_EFFECTSLOT_NULL, send, ffi::AL_FILTER_NULL);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(non_snake_case)]
+
+    use std::default::Default;
+
+    use effects::{EffectSlot, Effect, Reverb, Echo, is_supported, get_max_sends};
+    use error::Error;
+
+    #[test]
+    fn effect_slot_OK() -> () {
+        match EffectSlot::new(Effect::Reverb(Reverb::cave())) {
+            Ok(mut slot) => {
+                slot.set_volume(0.5);
+                assert_eq!(slot.get_volume(), 0.5);
+                assert!(slot.set_effect(Effect::Echo(Echo::default())).is_ok());
+            },
+            Err(err)     => {
+                assert!(!is_supported());
+                assert!(err == Error::EfxUnsupported);
+            }
+        }
+    }
+
+    #[test]
+    fn effect_max_sends_OK() -> () {
+        if is_supported() {
+            assert!(get_max_sends() > 0);
+        } else {
+            assert_eq!(get_max_sends(), 0);
+        }
+    }
+}
@@ -0,0 +1,150 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2014 Jeremy Letang (letang.jeremy@gmail.com)
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+//! The errors that can be returned by __ears__.
+
+use std::fmt;
+use std::error;
+
+use sndfile;
+
+/// The errors that an OpenAL call can raise.
+#[deriving(Clone, PartialEq, Show, Copy)]
+pub enum OpenAlError {
+    /// An invalid name parameter was passed to an OpenAL call.
+    InvalidName,
+    /// An invalid enum parameter was passed to an OpenAL call.
+    InvalidEnum,
+    /// An invalid value parameter was passed to an OpenAL call.
+    InvalidValue,
+    /// The requested OpenAL operation is illegal.
+    InvalidOperation,
+    /// OpenAL ran out of memory.
+    OutOfMemory,
+    /// An unknown OpenAL error code.
+    UnknownError(i32)
+}
+
+/// The errors that can occur when using __ears__.
+#[deriving(Clone, PartialEq)]
+pub enum Error {
+    /// A system error occured while accessing an audio file, e.g. the file
+    /// does not exist.
+    IoError(String),
+    /// The format of the audio file is not recognized by libsndfile.
+    UnrecognizedFormat(String),
+    /// The audio file is malformed.
+    MalformedFile(String),
+    /// The encoding of the audio file is not supported by libsndfile.
+    UnsupportedEncoding(String),
+    /// The number of channels of the audio file is not supported by OpenAL.
+    UnsupportedChannels(i32),
+    /// The number of samples is not a multiple of the number of channels.
+    IncompleteFrame,
+    /// The samplerate is not a positive number of frames per second.
+    InvalidSamplerate(i32),
+    /// An OpenAL call has failed.
+    OpenAlError(OpenAlError),
+    /// The audio device cannot be opened.
+    DeviceOpenFailed,
+    /// The OpenAL context cannot be created.
+    ContextCreationFailed,
+    /// The OpenAL context cannot be made current.
+    ContextActivationFailed,
+    /// The audio context cannot be created on a specific device while
+    /// another AudioContext is alive.
+    ContextAlreadyCreated,
+    /// The OpenAL implementation does not support loopback devices.
+    LoopbackUnsupported,
+    /// No input device is available on the system.
+    NoCaptureDevice,
+    /// The capture device cannot be opened.
+    CaptureDeviceOpenFailed,
+    /// The Recorder is not recording.
+    NotRecording,
+    /// The Recorder has no recorded samples to save.
+    NoRecordedSamples,
+    /// A Playlist cannot be created without tracks.
+    EmptyPlaylist,
+    /// The OpenAL implementation does not support the EFX extension.
+    EfxUnsupported
+}
+
+impl error::Error for Error {
+    fn description(&self) -> &str {
+        match *self {
+            Error::IoError(_)              => "input/output error",
+            Error::UnrecognizedFormat(_)   => "unrecognized audio format",
+            Error::MalformedFile(_)        => "malformed audio file",
+            Error::UnsupportedEncoding(_)  => "unsupported audio encoding",
+            Error::UnsupportedChannels(_)  => "unsupported channel count",
+            Error::IncompleteFrame         => "the last frame of samples is incomplete",
+            Error::InvalidSamplerate(_)    => "invalid samplerate",
+            Error::OpenAlError(_)          => "OpenAL error",
+            Error::DeviceOpenFailed        => "cannot open the audio device",
+            Error::ContextCreationFailed   => "cannot create the OpenAL context",
+            Error::ContextActivationFailed => "cannot make the OpenAL context current",
+            Error::ContextAlreadyCreated   => "an audio context is already created",
+            Error::LoopbackUnsupported     => "loopback devices are not supported",
+            Error::NoCaptureDevice         => "no input device available",
+            Error::CaptureDeviceOpenFailed => "cannot open the capture device",
+            Error::NotRecording            => "the recorder is not recording",
+            Error::NoRecordedSamples       => "no recorded samples",
+            Error::EmptyPlaylist           => "the playlist has no tracks",
+            Error::EfxUnsupported          => "effects are not supported"
+        }
+    }
+
+    fn detail(&self) -> Option<String> {
+        match *self {
+            Error::IoError(ref msg)             |
+            Error::UnrecognizedFormat(ref msg)  |
+            Error::MalformedFile(ref msg)       |
+            Error::UnsupportedEncoding(ref msg) => Some(msg.clone()),
+            Error::UnsupportedChannels(nb)      => Some(format!("{} channels", nb)),
+            Error::InvalidSamplerate(rate)      => Some(format!("{} Hz", rate)),
+            Error::OpenAlError(err)             => Some(format!("{}", err)),
+            _                                   => None
+        }
+    }
+}
+
+impl fmt::Show for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use std::error::Error as StdError;
+
+        match self.detail() {
+            Some(detail) => write!(f, "{}: {}", self.description(), detail),
+            None         => write!(f, "{}", self.description())
+        }
+    }
+}
+
+/// Build an Error from a libsndfile error code and its message.
+#[doc(hidden)]
+pub fn from_sndfile(err: sndfile::Error, message: String) -> Error {
+    match err {
+        sndfile::Error::UnrecognizedFormat  => Error::UnrecognizedFormat(message),
+        sndfile::Error::MalformedFile       => Error::MalformedFile(message),
+        sndfile::Error::UnsupportedEncoding => Error::UnsupportedEncoding(message),
+        _                                   => Error::IoError(message)
+    }
+}
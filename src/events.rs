@@ -0,0 +1,194 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2014 Jeremy Letang (letang.jeremy@gmail.com)
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+//! The events sent while the Sounds and the Musics are playing.
+//!
+//! The Musics send their events from their streaming task, the Sounds are
+//! watched by a single background task shared by the whole program, it
+//! sleeps while no Sound is watched.
+
+use std::mem;
+use std::time::Duration;
+use std::io::timer::sleep;
+use std::sync::{StaticMutex, MUTEX_INIT};
+
+use internal::OpenAlData;
+use context::AudioContext;
+use openal::{ffi, al};
+
+/// An event of the playback of a Sound or a Music.
+#[deriving(Clone, PartialEq, Show, Copy)]
+pub enum PlaybackEvent {
+    /// The playback has stopped, at the end of the samples or by a call to
+    /// stop
+    Stopped,
+    /// The playback has looped back to the start of the loop
+    Looped,
+    /// The streaming buffers ran out before being refilled, the playback
+    /// has been resumed with the next samples
+    Underrun
+}
+
+/// A source watched by the watcher task.
+struct Watched {
+    source: u32,
+    events: Sender<PlaybackEvent>,
+    state: i32,
+    sample_offset: i32,
+    /// Keep the source alive while it is watched
+    _context: AudioContext
+}
+
+impl Watched {
+    /// Send the events since the previous check of the source.
+    ///
+    /// Return false if the source can't be watched anymore.
+    fn check(&mut self) -> bool {
+        // The owner of the source may have been dropped
+        if !al::alIsSource(self.source) {
+            return false;
+        }
+        let state = al::alGetState(self.source);
+        let mut sample_offset = 0;
+        let mut looping = 0;
+        al::alGetSourcei(self.source, ffi::AL_SAMPLE_OFFSET, &mut sample_offset);
+        al::alGetSourcei(self.source, ffi::AL_LOOPING, &mut looping);
+
+        let event = match (self.state, state) {
+            (ffi::AL_PLAYING, ffi::AL_STOPPED)                  => Some(PlaybackEvent::Stopped),
+            (ffi::AL_PAUSED, ffi::AL_STOPPED)                   => Some(PlaybackEvent::Stopped),
+            (ffi::AL_PLAYING, ffi::AL_PLAYING)
+                if looping != 0 && sample_offset < self.sample_offset => Some(PlaybackEvent::Looped),
+            _                                                   => None
+        };
+        self.state = state;
+        self.sample_offset = sample_offset;
+        match event {
+            Some(event) => self.events.send_opt(event).is_ok(),
+            None        => true
+        }
+    }
+}
+
+/// The sources shared with the watcher task.
+struct Watcher {
+    watched: Vec<Watched>,
+    /// Wake the watcher task when it waits for sources
+    wake: Sender<()>
+}
+
+/// The interval between two checks of the watched sources, in milliseconds.
+const POLL: i64 = 10;
+
+/// Protect the access to WATCHER, held by the watcher task while it checks
+/// the sources.
+static WATCHER_LOCK: StaticMutex = MUTEX_INIT;
+/// The sources checked by the watcher task.
+static mut WATCHER: *mut Watcher = 0 as *mut Watcher;
+
+/// Run a function on the watched sources, start the watcher task if needed.
+fn with_watcher<T>(f: |&mut Watcher| -> T) -> T {
+    let _guard = WATCHER_LOCK.lock();
+    unsafe {
+        if WATCHER.is_null() {
+            let (wake, receiver) = channel();
+            spawn(proc() { run_watcher(receiver) });
+            WATCHER = mem::transmute(box Watcher {
+                watched: Vec::new(),
+                wake: wake
+            });
+        }
+        f(&mut *WATCHER)
+    }
+}
+
+/**
+ * Send the events of a source on a channel.
+ *
+ * The source is checked every 10 milliseconds, so a Sound stopped and played
+ * again between two checks sends no event. The channel replaces the previous
+ * channel of the source.
+ */
+#[doc(hidden)]
+pub fn watch(source: u32, events: Sender<PlaybackEvent>) -> () {
+    let context = match OpenAlData::check_al_context() {
+        Ok(context) => context,
+        Err(_)      => return
+    };
+    let mut sample_offset = 0;
+    al::alGetSourcei(source, ffi::AL_SAMPLE_OFFSET, &mut sample_offset);
+    let state = al::alGetState(source);
+    with_watcher(|watcher| {
+        watcher.watched.retain(|w| w.source != source);
+        watcher.watched.push(Watched {
+            source: source,
+            events: events,
+            state: state,
+            sample_offset: sample_offset,
+            _context: context
+        });
+        let _ = watcher.wake.send_opt(());
+    })
+}
+
+/**
+ * Stop sending the events of a source, before the source is deleted.
+ *
+ * Once this function returns, the watcher doesn't touch the source anymore.
+ */
+#[doc(hidden)]
+pub fn unwatch(source: u32) -> () {
+    let _guard = WATCHER_LOCK.lock();
+    unsafe {
+        if WATCHER.is_not_null() {
+            (*WATCHER).watched.retain(|w| w.source != source);
+        }
+    }
+}
+
+fn run_watcher(wake: Receiver<()>) -> () {
+    let poll = Duration::milliseconds(POLL);
+
+    loop {
+        let idle = with_watcher(|watcher| {
+            let mut i = 0;
+            while i < watcher.watched.len() {
+                if watcher.watched[i].check() {
+                    i += 1;
+                } else {
+                    watcher.watched.swap_remove(i);
+                }
+            }
+            watcher.watched.is_empty()
+        });
+
+        if idle {
+            // Nothing to do until a source is watched
+            if wake.recv_opt().is_err() {
+                return;
+            }
+        } else {
+            sleep(poll);
+        }
+        // Forget the wakes sent while the sources were checked
+        while wake.try_recv().is_ok() {}
+    }
+}
@@ -30,22 +30,21 @@ use std::task;
 use ears::{Sound, AudioController};
 
 fn main() -> () {
-    // call ears_init() function to ensure that the ears context is not destroyed by a task.
-    ears::init();
+    // keep the ears context alive until the end of the program.
+    let _context = ears::init().unwrap();
 
     let mut i = 0u;
 
     while i < 20 {
         task::spawn(proc() {
-            let mut snd2 = Sound::new("../res/shot.wav").expect("Error on Sound loading.");
+            let mut snd2 = Sound::new("../res/shot.wav").ok().expect("Error on Sound loading.");
             snd2.play();
-            while snd2.is_playing() {}
+            snd2.wait_until_stopped();
         });
         i += 1;
         sleep(Duration::milliseconds(150i64));
     }
 
-    // Wait until the last sound is played, the main task own the ears context,
-    // so we should kepp it alive
+    // Wait until the last sound is played
     sleep(Duration::milliseconds(900i64));
 }
\ No newline at end of file
@@ -27,19 +27,19 @@ use std::time::Duration;
 use std::io::timer::sleep;
 
 fn main() -> () {
-    // call ears_init() function to ensure that the ears context is not destroyed by a task.
-    ears::init();
+    // keep the ears context alive until the end of the program.
+    let _context = ears::init().unwrap();
 
     // initialize the RecordContext
-    let ctxt = ears::init_in().expect("initialization error !");
+    let ctxt = ears::init_in().ok().expect("initialization error !");
 
     // Create a new Recorder using the RecordContext
     let mut recorder = ears::Recorder::new(ctxt);
     recorder.start();
     sleep(Duration::milliseconds(5000i64));
-    recorder.stop();
+    recorder.stop().ok().expect("recording error !");
     match recorder.save_to_file("hello") {
-        true => println!("Save okay !"),
-        false => println!("Cannot save ...")
+        Ok(_)    => println!("Save okay !"),
+        Err(err) => println!("Cannot save ... {}", err)
     }
 }
\ No newline at end of file
@@ -42,8 +42,8 @@ fn main() {
 
     // Try to create the music
     let mut music = match Music::new(line.as_slice()) {
-        Some(music) => music,
-        None        => panic!("Cannot load the music.")
+        Ok(music) => music,
+        Err(err)  => panic!("Cannot load the music: {}", err)
     };
 
     // Play it
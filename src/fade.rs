@@ -0,0 +1,405 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2014 Jeremy Letang (letang.jeremy@gmail.com)
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+//! Progressive changes of the volume of the sources.
+//!
+//! The ramps are applied by a single background task shared by the whole
+//! program, it sleeps while no ramp is running.
+
+use std::mem;
+use std::cmp;
+use std::num::Float;
+use std::f32::consts::FRAC_PI_2;
+use std::time::Duration;
+use std::io::timer::sleep;
+use std::sync::{Arc, Mutex, StaticMutex, MUTEX_INIT};
+
+use time;
+
+use internal::OpenAlData;
+use context::AudioContext;
+use openal::{ffi, al};
+use music;
+use music::{Music, StopRequest};
+use mixer;
+use audio_controller::AudioController;
+
+/// The shape of a volume change.
+#[deriving(Clone, PartialEq, Show, Copy)]
+pub enum FadeCurve {
+    /// The volume changes at a constant rate.
+    Linear,
+    /// The volume follows a quarter of a sine, the sum of the powers of two
+    /// tracks crossfaded this way stays constant.
+    EqualPower
+}
+
+impl FadeCurve {
+    /// Get the value of a ramp from `from` to `to` at the progression `t`,
+    /// between 0 and 1.
+    fn value(&self, from: f32, to: f32, t: f32) -> f32 {
+        let shape = match *self {
+            FadeCurve::Linear                  => t,
+            FadeCurve::EqualPower if to >= from => (t * FRAC_PI_2).sin(),
+            FadeCurve::EqualPower              => 1. - (t * FRAC_PI_2).cos()
+        };
+        from + (to - from) * shape
+    }
+}
+
+/// A parameter of a source changed progressively by the ticker.
+#[doc(hidden)]
+pub struct Ramp {
+    pub source: u32,
+    pub param: i32,
+    pub from: f32,
+    pub to: f32,
+    pub curve: FadeCurve,
+    pub duration: Duration,
+    /// Stop the source at the end of the ramp, then set the parameter to
+    /// this value
+    pub stop: Option<f32>,
+    /// Set before stopping the source, so the streaming task of a Music
+    /// knows the stop is requested
+    pub stop_request: Option<Arc<Mutex<StopRequest>>>,
+    /// Notified at the end of the ramp
+    pub done: Option<Sender<()>>
+}
+
+/// A ramp applied by the ticker.
+struct Running {
+    ramp: Ramp,
+    /// The time the ramp started, in nanoseconds
+    start: u64,
+    /// Keep the sources alive until the end of the ramp
+    _context: AudioContext
+}
+
+impl Running {
+    /// Apply the ramp at its current progression.
+    ///
+    /// Return true if the ramp is over.
+    fn step(&mut self, now: u64) -> bool {
+        // The owner of the source may have been dropped
+        if !al::alIsSource(self.ramp.source) {
+            return true;
+        }
+        let elapsed = Duration::nanoseconds((now - self.start) as i64);
+        let t = if self.ramp.duration <= Duration::zero() {
+            1.
+        } else {
+            (elapsed.num_milliseconds() as f32 /
+             self.ramp.duration.num_milliseconds() as f32).min(1.)
+        };
+        if t < 1. {
+            set_param(self.ramp.source,
+                      self.ramp.param,
+                      self.ramp.curve.value(self.ramp.from, self.ramp.to, t));
+            return false;
+        }
+
+        // Land exactly on the requested value
+        set_param(self.ramp.source, self.ramp.param, self.ramp.to);
+
+        match self.ramp.stop {
+            Some(value) => {
+                match self.ramp.stop_request {
+                    Some(ref request) => {
+                        let mut request = request.lock();
+                        *request = StopRequest::Stop;
+                        al::alSourceStop(self.ramp.source);
+                    },
+                    None              => al::alSourceStop(self.ramp.source)
+                }
+                set_param(self.ramp.source, self.ramp.param, value);
+            },
+            None        => {}
+        }
+        match self.ramp.done.take() {
+            Some(done) => { let _ = done.send_opt(()); },
+            None       => {}
+        }
+        true
+    }
+}
+
+/// Set a parameter of a source, the volume goes through the mixer groups.
+fn set_param(source: u32, param: i32, value: f32) -> () {
+    if param == ffi::AL_GAIN {
+        mixer::set_gain(source, value);
+    } else {
+        al::alSourcef(source, param, value);
+    }
+}
+
+/// The ramps shared with the ticker task.
+struct Ticker {
+    running: Vec<Running>,
+    /// Wake the ticker task when it waits for ramps
+    wake: Sender<()>
+}
+
+/// The interval between two updates of the ramps, in milliseconds.
+const TICK: i64 = 10;
+
+/// Protect the access to TICKER, held by the ticker task while it applies
+/// the ramps.
+static TICKER_LOCK: StaticMutex = MUTEX_INIT;
+/// The ramps applied by the ticker task.
+static mut TICKER: *mut Ticker = 0 as *mut Ticker;
+
+/// Run a function on the shared ramps, start the ticker task if needed.
+fn with_ticker<T>(f: |&mut Ticker| -> T) -> T {
+    let _guard = TICKER_LOCK.lock();
+    unsafe {
+        if TICKER.is_null() {
+            let (wake, receiver) = channel();
+            spawn(proc() { run_ticker(receiver) });
+            TICKER = mem::transmute(box Ticker {
+                running: Vec::new(),
+                wake: wake
+            });
+        }
+        f(&mut *TICKER)
+    }
+}
+
+/**
+ * Start ramps on the shared ticker.
+ *
+ * The ramps sent together start on the same tick. A ramp replaces the
+ * running ramp of the same parameter of the same source, whose completion is
+ * then never notified.
+ */
+#[doc(hidden)]
+pub fn start_ramps(ramps: Vec<Ramp>) -> () {
+    let context = match OpenAlData::check_al_context() {
+        Ok(context) => context,
+        Err(_)      => return
+    };
+    let start = time::precise_time_ns();
+    with_ticker(|ticker| {
+        for ramp in ramps.into_iter() {
+            ticker.running.retain(|r| {
+                r.ramp.source != ramp.source || r.ramp.param != ramp.param
+            });
+            ticker.running.push(Running {
+                ramp: ramp,
+                start: start,
+                _context: context.clone()
+            });
+        }
+        let _ = ticker.wake.send_opt(());
+    })
+}
+
+/**
+ * Cancel the ramps of a source, before the source is deleted.
+ *
+ * Once this function returns, the ticker doesn't touch the source anymore
+ * and the completion of its ramps is never notified.
+ */
+#[doc(hidden)]
+pub fn cancel(source: u32) -> () {
+    let _guard = TICKER_LOCK.lock();
+    unsafe {
+        if TICKER.is_not_null() {
+            (*TICKER).running.retain(|r| r.ramp.source != source);
+        }
+    }
+}
+
+/**
+ * Start a ramp of a parameter of a source on the shared ticker.
+ *
+ * # Return
+ * A Receiver notified at the end of the ramp.
+ */
+#[doc(hidden)]
+pub fn ramp(source: u32,
+            param: i32,
+            from: f32,
+            to: f32,
+            duration: Duration,
+            curve: FadeCurve) -> Receiver<()> {
+    let (done, receiver) = channel();
+    start_ramps(vec!(Ramp {
+        source: source,
+        param: param,
+        from: from,
+        to: to,
+        curve: curve,
+        duration: cmp::max(duration, Duration::zero()),
+        stop: None,
+        stop_request: None,
+        done: Some(done)
+    }));
+    receiver
+}
+
+fn run_ticker(wake: Receiver<()>) -> () {
+    let tick = Duration::milliseconds(TICK);
+
+    loop {
+        let idle = with_ticker(|ticker| {
+            let now = time::precise_time_ns();
+            let mut i = 0;
+            while i < ticker.running.len() {
+                if ticker.running[i].step(now) {
+                    ticker.running.swap_remove(i);
+                } else {
+                    i += 1;
+                }
+            }
+            ticker.running.is_empty()
+        });
+
+        if idle {
+            // Nothing to do until a ramp is started
+            if wake.recv_opt().is_err() {
+                return;
+            }
+        } else {
+            sleep(tick);
+        }
+        // Forget the wakes sent while the ramps were applied
+        while wake.try_recv().is_ok() {}
+    }
+}
+
+/**
+ * Crossfade two Musics.
+ *
+ * The volume of `from` goes down to 0 while the volume of `to` goes up from
+ * 0 to its current volume, then `from` is stopped and gets back its volume.
+ * `to` is played if it is not playing.
+ *
+ * # Arguments
+ * * `from` - The outgoing Music
+ * * `to` - The incoming Music
+ * * `duration` - The duration of the crossfade
+ * * `curve` - The shape of the volume changes, FadeCurve::EqualPower keeps
+ * the loudness constant for uncorrelated tracks
+ *
+ * # Return
+ * A Receiver notified when the crossfade is done.
+ *
+ * # Example
+ * ```Rust
+ * use std::time::Duration;
+ * use ears::{Music, FadeCurve, AudioController};
+ *
+ * let mut level1 = Music::new("level1.ogg").unwrap();
+ * let mut level2 = Music::new("level2.ogg").unwrap();
+ * level1.play();
+ * // ...
+ * let done = ears::crossfade(&mut level1, &mut level2,
+ *                            Duration::seconds(2), FadeCurve::EqualPower);
+ * done.recv();
+ * ```
+ */
+pub fn crossfade(from: &mut Music,
+                 to: &mut Music,
+                 duration: Duration,
+                 curve: FadeCurve) -> Receiver<()> {
+    let (done, receiver) = channel();
+    let from_volume = from.get_volume();
+    let to_volume = to.get_volume();
+
+    to.set_volume(0.);
+    if !to.is_playing() {
+        to.play();
+    }
+    start_ramps(vec!(
+        Ramp {
+            source: music::get_source(to),
+            param: ffi::AL_GAIN,
+            from: 0.,
+            to: to_volume,
+            curve: curve,
+            duration: cmp::max(duration, Duration::zero()),
+            stop: None,
+            stop_request: None,
+            done: None
+        },
+        Ramp {
+            source: music::get_source(from),
+            param: ffi::AL_GAIN,
+            from: from_volume,
+            to: 0.,
+            curve: curve,
+            duration: cmp::max(duration, Duration::zero()),
+            stop: Some(from_volume),
+            stop_request: Some(music::stop_request(from)),
+            done: Some(done)
+        }
+    ));
+    receiver
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(non_snake_case)]
+
+    use std::num::Float;
+    use std::time::Duration;
+
+    use fade::{FadeCurve, crossfade};
+    use music::Music;
+    use states::State::{Playing, Stopped};
+    use audio_controller::AudioController;
+
+    #[test]
+    fn fade_curve_linear_OK() -> () {
+        assert_eq!(FadeCurve::Linear.value(0., 1., 0.), 0.);
+        assert_eq!(FadeCurve::Linear.value(0., 1., 0.25), 0.25);
+        assert_eq!(FadeCurve::Linear.value(1., 0., 0.25), 0.75);
+        assert_eq!(FadeCurve::Linear.value(1., 0., 1.), 0.);
+    }
+
+    #[test]
+    fn fade_curve_equal_power_OK() -> () {
+        let fade_in = FadeCurve::EqualPower.value(0., 1., 0.5);
+        let fade_out = FadeCurve::EqualPower.value(1., 0., 0.5);
+        assert!((fade_in * fade_in + fade_out * fade_out - 1.).abs() < 0.0001);
+        assert_eq!(FadeCurve::EqualPower.value(0., 1., 1.), 1.);
+        assert!(FadeCurve::EqualPower.value(1., 0., 1.).abs() < 0.0001);
+    }
+
+    #[test]
+    fn crossfade_OK() -> () {
+        let mut from = Music::new("res/shot.wav").ok().expect("Cannot create Music");
+        let mut to = Music::new("res/shot.wav").ok().expect("Cannot create Music");
+
+        from.set_looping(true);
+        from.set_volume(0.8);
+        to.set_looping(true);
+        from.play();
+        let done = crossfade(&mut from, &mut to, Duration::milliseconds(100),
+                             FadeCurve::Linear);
+        assert!(done.recv_opt().is_ok());
+        assert_eq!(from.get_state() as i32, Stopped as i32);
+        assert_eq!(from.get_volume(), 0.8);
+        assert_eq!(to.get_state() as i32, Playing as i32);
+        assert_eq!(to.get_volume(), 1.);
+        to.stop();
+    }
+}
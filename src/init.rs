@@ -22,54 +22,215 @@
 /*!
  * __ears__ initialization (optional).
  *
- * This module provide a unique function to initialize __ears__.
- * Use this function in the case of you don't use __ears__ for the first time
- * in you program in the main task. This prevent that the context was created
- * and destroyed in a another task.
+ * This module provide functions to initialize __ears__.
+ * The audio context is created by the first object which need it, and
+ * destroyed when the last one is dropped. Use init to keep the context alive
+ * as long as you need, e.g. to keep the listener settings between sounds.
  */
 
-use record_context::RecordContext;
+use std::ptr;
+use std::default::Default;
+
+use record_context::{RecordContext, CaptureFormat};
+use context::AudioContext;
 use internal::OpenAlData;
+use openal::{ffi, al};
+use error::Error;
 
 /**
  * Initialize the internal context
  *
  * # Return
- * true if initialization is made with success, false otherwise
+ * Ok(AudioContext) if initialization is made with success, the reason of the
+ * failure otherwise. The context is alive while the AudioContext is.
  *
  * # Example
  * ```Rust
  * match ears::init() {
- *     true  => { do_stuff() },
- *     false => panic!("ears init error")
+ *     Ok(context) => { do_stuff() },
+ *     Err(err)    => panic!("ears init error: {}", err)
+ * }
+ * ```
+ */
+pub fn init() -> Result<AudioContext, Error> {
+    OpenAlData::check_al_context()
+}
+
+/**
+ * Initialize the internal context on a specific output device
+ *
+ * The device names are given by `ears::devices()`. With OpenAL Soft the
+ * null backend device ("No Output") can be used to run without a sound card.
+ * The context is shared by the whole program, so this function must be
+ * called before any other __ears__ object is created.
+ *
+ * # Argument
+ * * `device_name` - The name of the output device to open
+ *
+ * # Return
+ * Ok(AudioContext) if the device is opened, Err(Error::ContextAlreadyCreated)
+ * if an AudioContext is alive, or the reason of the failure otherwise.
+ *
+ * # Example
+ * ```Rust
+ * let devices = ears::devices();
+ * match ears::init_with_device(devices[0].as_slice()) {
+ *     Ok(context) => { do_stuff() },
+ *     Err(err)    => panic!("ears init error: {}", err)
  * }
  * ```
  */
-pub fn init() -> bool {
-    match OpenAlData::check_al_context() {
-        Ok(_)    => true,
-        Err(err) => { println!("{}", err); false }
+pub fn init_with_device(device_name: &str) -> Result<AudioContext, Error> {
+    OpenAlData::open_al_context(device_name)
+}
+
+/**
+ * Initialize the internal context on a loopback device
+ *
+ * A loopback device doesn't play anything, the program pull the mixed
+ * samples with AudioContext::render. It needs the ALC_SOFT_loopback
+ * extension of OpenAL Soft, and is useful for headless test runs or to
+ * render sounds to a file.
+ *
+ * # Argument
+ * * `samplerate` - The samplerate of the rendered samples
+ *
+ * # Return
+ * Ok(AudioContext) if the loopback device is opened,
+ * Err(Error::LoopbackUnsupported) if the extension is missing,
+ * Err(Error::ContextAlreadyCreated) if an AudioContext is alive, or the
+ * reason of the failure otherwise.
+ *
+ * # Example
+ * ```Rust
+ * let context = ears::init_loopback(44100).unwrap();
+ * let mut samples = Vec::from_elem(1024, 0i16);
+ * context.render(samples.as_mut_slice());
+ * ```
+ */
+pub fn init_loopback(samplerate: i32) -> Result<AudioContext, Error> {
+    OpenAlData::open_loopback_context(samplerate)
+}
+
+/**
+ * List the available output devices
+ *
+ * Use ALC_ENUMERATE_ALL_EXT if it is present to list all the devices,
+ * otherwise fall back to ALC_ENUMERATION_EXT.
+ *
+ * # Return
+ * The names of the output devices, empty if the devices cannot be
+ * enumerated.
+ *
+ * # Example
+ * ```Rust
+ * for device in ears::devices().iter() {
+ *     println!("{}", device);
+ * }
+ * ```
+ */
+pub fn devices() -> Vec<String> {
+    if al::alcIsExtensionPresent(ptr::null_mut(), "ALC_ENUMERATE_ALL_EXT") {
+        al::alcGetStringList(ptr::null_mut(), ffi::ALC_ALL_DEVICES_SPECIFIER)
+    } else if al::alcIsExtensionPresent(ptr::null_mut(), "ALC_ENUMERATION_EXT") {
+        al::alcGetStringList(ptr::null_mut(), ffi::ALC_DEVICE_SPECIFIER)
+    } else {
+        Vec::new()
+    }
+}
+
+/**
+ * Get the name of the default output device
+ *
+ * # Return
+ * The name of the device opened by `ears::init()`, or None if OpenAL doesn't
+ * provide it.
+ */
+pub fn default_device() -> Option<String> {
+    if al::alcIsExtensionPresent(ptr::null_mut(), "ALC_ENUMERATE_ALL_EXT") {
+        al::alcGetString(ptr::null_mut(), ffi::ALC_DEFAULT_ALL_DEVICES_SPECIFIER)
+    } else {
+        al::alcGetString(ptr::null_mut(), ffi::ALC_DEFAULT_DEVICE_SPECIFIER)
     }
 }
 
 /**
  * Initialize the input device context
  *
+ * The input context can be requested from any task.
+ *
  * # Return
- * true if initialization is made with success, false otherwise
+ * Ok(RecordContext) if initialization is made with success, the reason of the
+ * failure otherwise.
  *
  * # Example
  * ```Rust
  * match ears::init_in() {
- *     Some(rc) => { do_stuff() },
- *     None     => panic!("ears init input error")
+ *     Ok(rc)   => { do_stuff() },
+ *     Err(err) => panic!("ears init input error: {}", err)
+ * }
+ * ```
+ */
+pub fn init_in() -> Result<RecordContext, Error> {
+    OpenAlData::check_al_input_context(None, Default::default())
+}
+
+/**
+ * Initialize the input device context with a specific device and format
+ *
+ * # Arguments
+ * * `device_name` - The name of the capture device to open, as returned by
+ * ears::capture_devices(), or None to open the default one
+ * * `format` - The samplerate, the number of channels (1 or 2) and the
+ * buffer size of the captured samples
+ *
+ * # Return
+ * Ok(RecordContext) if the capture device is opened, the reason of the
+ * failure otherwise.
+ *
+ * # Example
+ * ```Rust
+ * use ears::CaptureFormat;
+ *
+ * let format = CaptureFormat { samplerate: 48000, channels: 2, buffer_size: 4800 };
+ * match ears::init_in_with(None, format) {
+ *     Ok(rc)   => { do_stuff() },
+ *     Err(err) => panic!("ears init input error: {}", err)
  * }
  * ```
  */
-pub fn init_in() -> Option<RecordContext> {
-    match OpenAlData::check_al_input_context() {
-        Ok(ctxt) => Some(ctxt),
-        Err(err) => { println!("{}", err); None }
+pub fn init_in_with(device_name: Option<&str>,
+                    format: CaptureFormat) -> Result<RecordContext, Error> {
+    OpenAlData::check_al_input_context(device_name, format)
+}
+
+/**
+ * List the available capture devices
+ *
+ * # Return
+ * The names of the capture devices, empty if the devices cannot be
+ * enumerated.
+ */
+pub fn capture_devices() -> Vec<String> {
+    if al::alcIsExtensionPresent(ptr::null_mut(), "ALC_EXT_CAPTURE") {
+        al::alcGetStringList(ptr::null_mut(), ffi::ALC_CAPTURE_DEVICE_SPECIFIER)
+    } else {
+        Vec::new()
+    }
+}
+
+/**
+ * Get the name of the default capture device
+ *
+ * # Return
+ * The name of the device opened by `ears::init_in()`, or None if OpenAL
+ * doesn't provide it.
+ */
+pub fn default_capture_device() -> Option<String> {
+    if al::alcIsExtensionPresent(ptr::null_mut(), "ALC_EXT_CAPTURE") {
+        al::alcGetString(ptr::null_mut(), ffi::ALC_CAPTURE_DEFAULT_DEVICE_SPECIFIER)
+    } else {
+        None
     }
 }
 
@@ -77,32 +238,117 @@ pub fn init_in() -> Option<RecordContext> {
 mod test {
     #![allow(non_snake_case)]
 
+    use std::time::Duration;
+    use std::io::timer::sleep;
+    use std::sync::{StaticMutex, MUTEX_INIT};
+
     use init;
     use init_in;
+    use init_in_with;
+    use init_with_device;
+    use devices;
+    use default_device;
+    use error::Error;
+    use record_context::CaptureFormat;
+
+    /// Serialize the tests which create or check the shared context, those
+    /// of the other modules can still keep it alive.
+    static CONTEXT_LOCK: StaticMutex = MUTEX_INIT;
 
     #[test]
     fn test_init_ears_OK() -> () {
-        assert_eq!(init(), true)
+        let _guard = CONTEXT_LOCK.lock();
+        assert!(init().is_ok())
     }
 
     #[test]
     #[ignore]
     fn test_init_in_with_normal_init_OK() -> () {
-        init();
-        assert!(init_in().is_some())
+        let _context = init().unwrap();
+        assert!(init_in().is_ok())
     }
 
     #[test]
     #[ignore]
     fn test_init_in_alone_OK() -> () {
-        assert!(init_in().is_some())
+        assert!(init_in().is_ok())
     }
 
     #[test]
     fn test_init_in_in_another_task_OK() -> () {
-        init();
+        let _guard = CONTEXT_LOCK.lock();
+        let _context = init().unwrap();
+        // The same result as in this task, an error if there is no capture
+        // device
+        let expected = init_in().err();
+        let (sender, receiver) = channel();
         spawn(proc() {
-            assert_eq!(init_in(), None)
-        })
+            sender.send(init_in().err())
+        });
+        assert_eq!(receiver.recv(), expected)
+    }
+
+    #[test]
+    #[ignore]
+    fn test_init_in_with_stereo_OK() -> () {
+        let format = CaptureFormat { samplerate: 22050, channels: 2, buffer_size: 2205 };
+        let record_context = init_in_with(None, format).unwrap();
+        assert_eq!(record_context.get_format(), format)
+    }
+
+    #[test]
+    fn test_init_in_with_unsupported_channels_FAIL() -> () {
+        let format = CaptureFormat { samplerate: 44100, channels: 3, buffer_size: 44100 };
+        assert_eq!(init_in_with(None, format).err(),
+                   Some(Error::UnsupportedChannels(3)))
+    }
+
+    #[test]
+    fn test_init_shared_between_tasks_OK() -> () {
+        let _guard = CONTEXT_LOCK.lock();
+        let context = init().unwrap();
+        let (sender, receiver) = channel();
+        spawn(proc() {
+            let _context = context;
+            sender.send(init().is_ok())
+        });
+        assert!(receiver.recv())
+    }
+
+    #[test]
+    fn test_devices_OK() -> () {
+        let devices = devices();
+        // Nothing to check on a machine without output device
+        match default_device() {
+            Some(ref device) if !devices.is_empty() => assert!(devices.contains(device)),
+            _                                       => {}
+        }
+    }
+
+    #[test]
+    fn test_init_with_device_while_context_alive_FAIL() -> () {
+        let _guard = CONTEXT_LOCK.lock();
+        let _context = init().unwrap();
+        let devices = devices();
+        if devices.is_empty() {
+            return;
+        }
+        assert_eq!(init_with_device(devices[0].as_slice()).err(),
+                   Some(Error::ContextAlreadyCreated))
+    }
+
+    #[test]
+    fn test_init_with_device_unknown_FAIL() -> () {
+        let _guard = CONTEXT_LOCK.lock();
+        // Retry while the tests of the other modules keep a context alive
+        let mut result = init_with_device("ears unknown device").err();
+        for _ in range(0u, 1000) {
+            if result != Some(Error::ContextAlreadyCreated) {
+                break;
+            }
+            sleep(Duration::milliseconds(10));
+            result = init_with_device("ears unknown device").err();
+        }
+        assert_eq!(result, Some(Error::DeviceOpenFailed))
     }
 }
@@ -0,0 +1,243 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2014 Jeremy Letang (letang.jeremy@gmail.com)
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+//! Input level metering and voice activity detection.
+
+use std::num::Float;
+use std::time::Duration;
+
+/// The level of a chunk of samples, between 0 and 1.
+#[deriving(Clone, PartialEq, Show, Copy)]
+pub struct InputLevel {
+    /// The root mean square of the samples.
+    pub rms: f32,
+    /// The highest absolute value of the samples.
+    pub peak: f32
+}
+
+impl InputLevel {
+    /**
+     * Measure the level of a chunk of samples.
+     *
+     * # Argument
+     * * `samples` - The samples to measure, interleaved if there is several
+     * channels
+     *
+     * # Return
+     * The level of the samples, zero for an empty chunk.
+     */
+    pub fn from_samples(samples: &[i16]) -> InputLevel {
+        if samples.is_empty() {
+            return InputLevel { rms: 0., peak: 0. };
+        }
+        let mut sum = 0f64;
+        let mut peak = 0f32;
+        for sample in samples.iter() {
+            let value = *sample as f32 / 32768.;
+            sum += (value * value) as f64;
+            peak = peak.max(value.abs());
+        }
+        InputLevel {
+            rms: (sum / samples.len() as f64).sqrt() as f32,
+            peak: peak
+        }
+    }
+}
+
+/// The settings of a VoiceActivityDetector.
+#[deriving(Clone, PartialEq, Show, Copy)]
+pub struct VoiceDetection {
+    /// The rms level above which a chunk contains voice.
+    pub threshold: f32,
+    /// How long the voice stays active after the level fall below the
+    /// threshold, so the pauses between words don't split a segment.
+    pub hangover: Duration,
+    /// If true, the Recorder only delivers the samples captured while the
+    /// voice is active.
+    pub gate: bool
+}
+
+/// The voice activity events sent by a Recorder.
+#[deriving(Clone, PartialEq, Show, Copy)]
+pub enum VoiceEvent {
+    /// The voice became active, a segment starts at the given frame of the
+    /// record.
+    SegmentStarted(i64),
+    /// The voice became inactive, the segment ends at the given frame of the
+    /// record.
+    SegmentEnded(i64)
+}
+
+/**
+ * A simple energy based voice activity detector.
+ *
+ * The voice becomes active as soon as a chunk is louder than the threshold,
+ * and inactive when the level stays below the threshold for the hangover
+ * duration.
+ *
+ * # Example
+ * ```Rust
+ * use std::time::Duration;
+ * use ears::{InputLevel, VoiceDetection, VoiceActivityDetector};
+ *
+ * let mut vad = VoiceActivityDetector::new(VoiceDetection {
+ *     threshold: 0.05,
+ *     hangover: Duration::milliseconds(300),
+ *     gate: false
+ * });
+ * let level = InputLevel::from_samples(chunk.as_slice());
+ * if vad.process(level, Duration::milliseconds(20)) {
+ *     println!("Someone is talking");
+ * }
+ * ```
+ */
+#[deriving(Clone, Show)]
+pub struct VoiceActivityDetector {
+    settings: VoiceDetection,
+    active: bool,
+    silence: Duration
+}
+
+impl VoiceActivityDetector {
+    /// Create a detector, the voice is initially inactive.
+    pub fn new(settings: VoiceDetection) -> VoiceActivityDetector {
+        VoiceActivityDetector {
+            settings: settings,
+            active: false,
+            silence: Duration::zero()
+        }
+    }
+
+    /**
+     * Update the detector with the level of a new chunk.
+     *
+     * # Arguments
+     * * `level` - The level of the chunk
+     * * `duration` - The duration of the chunk
+     *
+     * # Return
+     * true if the voice is active after this chunk, false otherwise.
+     */
+    pub fn process(&mut self, level: InputLevel, duration: Duration) -> bool {
+        if level.rms >= self.settings.threshold {
+            self.active = true;
+            self.silence = Duration::zero();
+        } else if self.active {
+            self.silence = self.silence + duration;
+            if self.silence >= self.settings.hangover {
+                self.active = false;
+            }
+        }
+        self.active
+    }
+
+    /// Check if the voice is active.
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    /// Make the voice inactive and forget the silence already measured.
+    pub fn reset(&mut self) -> () {
+        self.active = false;
+        self.silence = Duration::zero();
+    }
+
+    /// Get the settings of the detector.
+    pub fn get_settings(&self) -> VoiceDetection {
+        self.settings
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(non_snake_case)]
+
+    use std::time::Duration;
+    use input_level::{InputLevel, VoiceDetection, VoiceActivityDetector};
+
+    fn detector() -> VoiceActivityDetector {
+        VoiceActivityDetector::new(VoiceDetection {
+            threshold: 0.1,
+            hangover: Duration::milliseconds(100),
+            gate: false
+        })
+    }
+
+    #[test]
+    fn input_level_silence_OK() -> () {
+        let level = InputLevel::from_samples([0i16, ..64].as_slice());
+        assert_eq!(level, InputLevel { rms: 0., peak: 0. });
+        let level = InputLevel::from_samples([].as_slice());
+        assert_eq!(level, InputLevel { rms: 0., peak: 0. });
+    }
+
+    #[test]
+    fn input_level_square_OK() -> () {
+        let samples = [16384i16, -16384, 16384, -16384];
+        let level = InputLevel::from_samples(samples.as_slice());
+        assert_eq!(level, InputLevel { rms: 0.5, peak: 0.5 });
+    }
+
+    #[test]
+    fn voice_activity_detector_hangover_OK() -> () {
+        let mut vad = detector();
+        let loud = InputLevel { rms: 0.2, peak: 0.5 };
+        let quiet = InputLevel { rms: 0.01, peak: 0.02 };
+        let chunk = Duration::milliseconds(40);
+
+        assert!(!vad.process(quiet, chunk));
+        assert!(vad.process(loud, chunk));
+        assert!(vad.process(quiet, chunk));
+        assert!(vad.process(quiet, chunk));
+        assert!(!vad.process(quiet, chunk));
+        assert!(!vad.is_active());
+    }
+
+    #[test]
+    fn voice_activity_detector_reset_hangover_OK() -> () {
+        let mut vad = detector();
+        let loud = InputLevel { rms: 0.2, peak: 0.5 };
+        let quiet = InputLevel { rms: 0.01, peak: 0.02 };
+        let chunk = Duration::milliseconds(60);
+
+        assert!(vad.process(loud, chunk));
+        assert!(vad.process(quiet, chunk));
+        assert!(vad.process(loud, chunk));
+        assert!(vad.process(quiet, chunk));
+        assert!(!vad.process(quiet, chunk));
+    }
+
+    #[test]
+    fn voice_activity_detector_reset_OK() -> () {
+        let mut vad = detector();
+        let loud = InputLevel { rms: 0.2, peak: 0.5 };
+        let quiet = InputLevel { rms: 0.01, peak: 0.02 };
+        let chunk = Duration::milliseconds(60);
+
+        assert!(vad.process(loud, chunk));
+        assert!(vad.process(quiet, chunk));
+        vad.reset();
+        assert!(!vad.is_active());
+        // The silence measured before the reset is forgotten
+        assert!(vad.process(loud, chunk));
+        assert!(vad.process(quiet, chunk));
+    }
+}
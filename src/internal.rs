@@ -21,172 +21,322 @@
 
 //! Internal class to handle OpenAl context and device.
 //!
-//! Work as a Singleton, check_al_context must be called before each OpenAl object
-//! to be sure that the context is created.
+//! The OpenAL context is shared by the whole process, check_al_context must be
+//! called before each OpenAl object to be sure that the context is created,
+//! the returned AudioContext keep it alive.
 
 #![macro_escape]
-#![allow(raw_pointer_deriving)]
 
-use std::cell::RefCell;
 use std::ptr;
-use openal::ffi;
+use std::mem;
+use std::sync::{Arc, Weak, StaticMutex, MUTEX_INIT};
+use libc::c_char;
+use openal::{ffi, al};
 use record_context;
-use record_context::RecordContext;
+use record_context::{RecordContext, CaptureFormat};
+use context;
+use context::AudioContext;
+use error::Error;
 
-thread_local!(static AL_CONTEXT: RefCell<Box<OpenAlData>> = RefCell::new(box OpenAlData::default()))
+/// Protect the access to AL_CONTEXT.
+static AL_CONTEXT_LOCK: StaticMutex = MUTEX_INIT;
+/// The shared OpenAL context, alive while an AudioContext use it.
+static mut AL_CONTEXT: *mut Weak<OpenAlData> = 0 as *mut Weak<OpenAlData>;
+/// Protect the change of the current OpenAL context.
+///
+/// Unlike AL_CONTEXT_LOCK, it can be taken when the last AudioContext is
+/// dropped while AL_CONTEXT_LOCK is held.
+static CURRENT_LOCK: StaticMutex = MUTEX_INIT;
 
-#[deriving(Clone)]
 pub struct OpenAlData {
     pub al_context: *mut ffi::ALCcontext,
     pub al_device: *mut ffi::ALCdevice,
-    pub al_capt_device: *mut ffi::ALCdevice
+    pub render_samples: Option<ffi::LPALCRENDERSAMPLESSOFT>,
+    pub efx: Option<ffi::Efx>
 }
 
+unsafe impl Send for OpenAlData {}
+unsafe impl Sync for OpenAlData {}
+
 impl OpenAlData {
     /// Create a new OpenAlData struct
     ///
+    /// Open the device with the given name, or the default device if no name
+    /// is given.
+    ///
     /// Private method.
-    fn new() -> Result<OpenAlData, String> {
-        let device = unsafe { ffi::alcOpenDevice(ptr::null_mut()) };
+    fn new(device_name: Option<&str>) -> Result<OpenAlData, Error> {
+        let device = match device_name {
+            Some(name) => name.with_c_str(|c_str| unsafe {
+                ffi::alcOpenDevice(c_str as *mut c_char)
+            }),
+            None       => unsafe { ffi::alcOpenDevice(ptr::null_mut()) }
+        };
         if device.is_null() {
-            return Err("Internal error: cannot open the default device.".to_string());
+            return Err(Error::DeviceOpenFailed);
+        }
+        OpenAlData::with_device(device, ptr::null_mut(), None)
+    }
+
+    /// Create a new OpenAlData struct on an OpenAL Soft loopback device
+    ///
+    /// The loopback device render 16 bits stereo samples at the given
+    /// samplerate, only when AudioContext::render is called.
+    ///
+    /// Private method.
+    fn new_loopback(samplerate: i32) -> Result<OpenAlData, Error> {
+        if !al::alcIsExtensionPresent(ptr::null_mut(), "ALC_SOFT_loopback") {
+            return Err(Error::LoopbackUnsupported);
         }
-        let context = unsafe { ffi::alcCreateContext(device, ptr::null_mut()) };
+        let open_device = al::alcGetProcAddress(ptr::null_mut(),
+                                                "alcLoopbackOpenDeviceSOFT");
+        let render_samples = al::alcGetProcAddress(ptr::null_mut(),
+                                                   "alcRenderSamplesSOFT");
+        if open_device.is_null() || render_samples.is_null() {
+            return Err(Error::LoopbackUnsupported);
+        }
+        let (open_device, render_samples): (ffi::LPALCLOOPBACKOPENDEVICESOFT,
+                                            ffi::LPALCRENDERSAMPLESSOFT) = unsafe {
+            (mem::transmute(open_device), mem::transmute(render_samples))
+        };
+
+        let device = open_device(ptr::null());
+        if device.is_null() {
+            return Err(Error::DeviceOpenFailed);
+        }
+        let mut attributes = [ffi::ALC_FORMAT_CHANNELS_SOFT, ffi::ALC_STEREO_SOFT,
+                              ffi::ALC_FORMAT_TYPE_SOFT, ffi::ALC_SHORT_SOFT,
+                              ffi::ALC_FREQUENCY, samplerate,
+                              0];
+        OpenAlData::with_device(device, attributes.as_mut_ptr(), Some(render_samples))
+    }
+
+    /// Create the context on an opened device and make it current
+    ///
+    /// The device is closed if the context cannot be created.
+    ///
+    /// Private method.
+    fn with_device(device: *mut ffi::ALCdevice,
+                   attributes: *mut i32,
+                   render_samples: Option<ffi::LPALCRENDERSAMPLESSOFT>)
+                   -> Result<OpenAlData, Error> {
+        let context = unsafe { ffi::alcCreateContext(device, attributes) };
         if context.is_null() {
-            return Err("Internal error: cannot create the OpenAL context.".to_string());
+            unsafe { ffi::alcCloseDevice(device); }
+            return Err(Error::ContextCreationFailed);
         }
-        if unsafe { ffi::alcMakeContextCurrent(context) } == ffi::ALC_FALSE {
-            return Err("Internal error: cannot make the OpenAL context current.".to_string());
+        let made_current = {
+            let _guard = CURRENT_LOCK.lock();
+            unsafe { ffi::alcMakeContextCurrent(context) }
+        };
+        if made_current == ffi::ALC_FALSE {
+            unsafe {
+                ffi::alcDestroyContext(context);
+                ffi::alcCloseDevice(device);
+            }
+            return Err(Error::ContextActivationFailed);
         }
 
         Ok(
             OpenAlData {
                 al_context: context,
                 al_device: device,
-                al_capt_device: ptr::null_mut()
+                render_samples: render_samples,
+                efx: al::load_efx(device)
             }
         )
     }
 
-    fn default() -> OpenAlData {
-        OpenAlData {
-            al_context: ptr::null_mut(),
-            al_device: ptr::null_mut(),
-            al_capt_device: ptr::null_mut()
+    /// Get the shared context if it is still alive.
+    ///
+    /// AL_CONTEXT_LOCK must be locked by the caller.
+    unsafe fn get_shared() -> Option<Arc<OpenAlData>> {
+        if AL_CONTEXT.is_not_null() {
+            (*AL_CONTEXT).upgrade()
+        } else {
+            None
         }
     }
 
-    fn is_default(&self) -> bool {
-        if self.al_context.is_null() &&
-           self.al_device.is_null() &&
-           self.al_capt_device.is_null() {
-            false
+    /// Replace the shared context.
+    ///
+    /// AL_CONTEXT_LOCK must be locked by the caller.
+    unsafe fn set_shared(al_data: OpenAlData) -> AudioContext {
+        let al_data = Arc::new(al_data);
+        if AL_CONTEXT.is_null() {
+            AL_CONTEXT = mem::transmute(box al_data.downgrade());
         } else {
-            true
+            *AL_CONTEXT = al_data.downgrade();
         }
+        context::new(al_data)
     }
 
     /// Check if the context is created.
     ///
     /// This function check is the OpenAl context is already created.
-    /// If context doesn't exist, create it, and store it in a local_data,
-    /// else get it from the local data and return it.
+    /// If context doesn't exist, create it on the default device, and share
+    /// it with all the tasks, else return the existing one.
     ///
     /// # Return
-    /// A result containing nothing if the OpenAlData struct exist,
-    /// otherwise an error message.
-    pub fn check_al_context() -> Result<(), String> {
-        if unsafe { ffi::alcGetCurrentContext().is_not_null() } {
-            return Ok(())
+    /// A result containing a handle to the shared context, otherwise the
+    /// reason of the failure.
+    pub fn check_al_context() -> Result<AudioContext, Error> {
+        let _guard = AL_CONTEXT_LOCK.lock();
+        unsafe {
+            match OpenAlData::get_shared() {
+                Some(al_data) => Ok(context::new(al_data)),
+                None          => Ok(OpenAlData::set_shared(try!(OpenAlData::new(None))))
+            }
         }
-        AL_CONTEXT.with(|f| {
-            let is_def = f.borrow_mut().is_default();
-            if is_def {
-                match OpenAlData::new() {
-                    Ok(al_data) => {
-                        *f.borrow_mut() = box al_data; Ok(())
-                    },
-                    Err(err) => Err(err)
-                }
-            } else {
-                Ok(())
+    }
+
+    /// Get the shared context if an AudioContext is alive.
+    ///
+    /// Unlike check_al_context, the context is never created.
+    ///
+    /// # Return
+    /// A handle to the shared context, None if no AudioContext is alive.
+    pub fn get_al_context() -> Option<AudioContext> {
+        let _guard = AL_CONTEXT_LOCK.lock();
+        unsafe { OpenAlData::get_shared().map(|al_data| context::new(al_data)) }
+    }
+
+    /// Create the shared context on a specific device.
+    ///
+    /// # Argument
+    /// * `device_name` - The name of the device to open, as returned by
+    /// ears::devices()
+    ///
+    /// # Return
+    /// A result containing a handle to the shared context, otherwise the
+    /// reason of the failure. Fail with Error::ContextAlreadyCreated if an
+    /// AudioContext is still alive.
+    pub fn open_al_context(device_name: &str) -> Result<AudioContext, Error> {
+        let _guard = AL_CONTEXT_LOCK.lock();
+        unsafe {
+            match OpenAlData::get_shared() {
+                Some(_) => Err(Error::ContextAlreadyCreated),
+                None    => Ok(OpenAlData::set_shared(try!(OpenAlData::new(Some(device_name)))))
             }
-        })
+        }
     }
 
-    fn is_input_context_init() -> Result<RecordContext, String> {
-        // let is_some = AL_CONTEXT.get().is_some();
-        AL_CONTEXT.with(|f| {
-            let is_def = f.borrow_mut().is_default();
-            if !is_def {
-                let mut new_context = f.borrow_mut();
-                if new_context.al_capt_device.is_not_null() {
-                    Ok(record_context::new(new_context.al_capt_device))
-                } else {
-                    if "ALC_EXT_CAPTURE".with_c_str(|c_str| unsafe {
-                        ffi::alcIsExtensionPresent(new_context.al_device, c_str) }) == ffi::ALC_FALSE {
-                        return Err("Error: no input device available on your system.".to_string())
-                    } else {
-                        new_context.al_capt_device = unsafe {
-                        ffi::alcCaptureOpenDevice(ptr::null_mut(),
-                                                  44100,
-                                                  ffi::AL_FORMAT_MONO16,
-                                                  44100) };
-                        if new_context.al_capt_device.is_null() {
-                            return Err("Internal error: cannot open the default capture device.".to_string())
-                        } else {
-                           let cap_device = new_context.al_capt_device;
-                           return Ok(record_context::new(cap_device))
-                        }
-                    }
-                    Err("Error: you must request the input context, \
-                        in the task where you initialize ears.".to_string())
-                }
-            } else {
-                Err("Error: you must request the input context, \
-                    in the task where you initialize ears.".to_string())
+    /// Create the shared context on an OpenAL Soft loopback device.
+    ///
+    /// # Argument
+    /// * `samplerate` - The samplerate of the rendered samples
+    ///
+    /// # Return
+    /// A result containing a handle to the shared context, otherwise the
+    /// reason of the failure. Fail with Error::ContextAlreadyCreated if an
+    /// AudioContext is still alive.
+    pub fn open_loopback_context(samplerate: i32) -> Result<AudioContext, Error> {
+        let _guard = AL_CONTEXT_LOCK.lock();
+        unsafe {
+            match OpenAlData::get_shared() {
+                Some(_) => Err(Error::ContextAlreadyCreated),
+                None    => Ok(OpenAlData::set_shared(try!(OpenAlData::new_loopback(samplerate))))
             }
-        })
+        }
     }
 
-    /// Check if the input context is created.
+    /// Open a capture device and create a RecordContext.
     ///
-    /// This function check if the input OpenAl context is already created.
-    /// The input openAL context need the normal AL context + its own extension.
-    /// So check if the context exist first, then load the input extension.
+    /// The capture device need the normal AL context + its own extension.
+    /// So get the context first, then check the input extension.
+    ///
+    /// # Arguments
+    /// * `device_name` - The name of the capture device to open, or None to
+    /// open the default one
+    /// * `format` - The format of the captured samples
     ///
     /// # Return
-    /// A result containing nothing if the OpenAlData struct exist,
-    /// otherwise an error message.
-    pub fn check_al_input_context() -> Result<RecordContext, String> {
-        if unsafe { ffi::alcGetCurrentContext().is_not_null() } {
-            OpenAlData::is_input_context_init()
-        } else {
-            match OpenAlData::check_al_context() {
-                Ok(_)    => OpenAlData::is_input_context_init(),
-                Err(err) => Err(err)
+    /// A result containing the RecordContext if the capture device is opened,
+    /// otherwise the reason of the failure.
+    pub fn check_al_input_context(device_name: Option<&str>,
+                                  format: CaptureFormat)
+                                  -> Result<RecordContext, Error> {
+        let al_format = match format.channels {
+            1 => ffi::AL_FORMAT_MONO16,
+            2 => ffi::AL_FORMAT_STEREO16,
+            _ => return Err(Error::UnsupportedChannels(format.channels))
+        };
+        let ctxt = try!(OpenAlData::check_al_context());
+        if !al::alcIsExtensionPresent(context::get(&ctxt).al_device, "ALC_EXT_CAPTURE") {
+            return Err(Error::NoCaptureDevice);
+        }
+        let capt_device = match device_name {
+            Some(name) => name.with_c_str(|c_str| unsafe {
+                ffi::alcCaptureOpenDevice(c_str as *mut c_char,
+                                          format.samplerate,
+                                          al_format,
+                                          format.buffer_size)
+            }),
+            None       => unsafe {
+                ffi::alcCaptureOpenDevice(ptr::null_mut(),
+                                          format.samplerate,
+                                          al_format,
+                                          format.buffer_size)
             }
+        };
+        if capt_device.is_null() {
+            return Err(Error::CaptureDeviceOpenFailed);
         }
+        Ok(record_context::new(ctxt, CaptureDevice {
+            device: capt_device,
+            format: format
+        }))
     }
 }
 
 impl Drop for OpenAlData {
     fn drop(&mut self) {
         unsafe {
-            ffi::alcDestroyContext(self.al_context);
-            if self.al_capt_device.is_not_null() {
-                ffi::alcCaptureCloseDevice(self.al_capt_device);
+            {
+                // A new shared context may already be current
+                let _guard = CURRENT_LOCK.lock();
+                if ffi::alcGetCurrentContext() == self.al_context {
+                    ffi::alcMakeContextCurrent(ptr::null_mut());
+                }
             }
+            ffi::alcDestroyContext(self.al_context);
             ffi::alcCloseDevice(self.al_device);
         }
     }
 }
 
+/// An opened capture device, closed when dropped.
+pub struct CaptureDevice {
+    pub device: *mut ffi::ALCdevice,
+    pub format: CaptureFormat
+}
+
+unsafe impl Send for CaptureDevice {}
+unsafe impl Sync for CaptureDevice {}
+
+impl Drop for CaptureDevice {
+    fn drop(&mut self) {
+        unsafe { ffi::alcCaptureCloseDevice(self.device); }
+    }
+}
+
+/// Check the OpenAL context before an OpenAL call.
+///
+/// Without argument the context is created if needed and the failure is
+/// returned as an Err. Otherwise the given value is returned if no
+/// AudioContext is alive, so a setter doesn't open a device only to close it
+/// right after, and the functions using this form must document it.
 macro_rules! check_openal_context(
-    ($def_ret:expr) => (
+    () => (
             match OpenAlData::check_al_context() {
                 Ok(_)    => {},
-                Err(err) => { println!("{}", err); return $def_ret; }
+                Err(err) => return Err(err)
+            }
+        );
+    ($def_ret:expr) => (
+            match OpenAlData::get_al_context() {
+                Some(_) => {},
+                None    => return $def_ret
             }
         );
 )
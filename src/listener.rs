@@ -20,9 +20,17 @@
 // CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
 
 //! Module for manage the listener in the scene.
+//!
+//! The listener settings belong to the audio context, keep the AudioContext
+//! returned by `ears::init()` alive to keep them when no sound exists.
+//!
+//! The functions of this module don't report errors: if no AudioContext is
+//! alive, the setters do nothing and the getters return a default value.
 
 use internal::OpenAlData;
 use openal::{ffi, al};
+use distance_model;
+use distance_model::DistanceModel;
 
 /**
  * Set the global volume of the scene.
@@ -158,13 +166,202 @@ pub fn get_orientation() -> ([f32, ..3], [f32, ..3]) {
      [orientation[3], orientation[4], orientation[5]])
 }
 
+/**
+ * Set the velocity of the listener.
+ *
+ * The velocity doesn't move the listener, it is only used to compute the
+ * Doppler effect.
+ *
+ * Default is [0., 0., 0.].
+ *
+ * # Argument
+ * * `velocity` - A three dimensional vector of f32 containing the velocity
+ * of the listener [x, y, z], in units per second.
+ *
+ * # Example
+ * ```Rust
+ * # use ears::listener;
+ * listener::set_velocity([0f32, 0f32, -20f32]);
+ * ```
+ */
+pub fn set_velocity(velocity: [f32, ..3]) -> () {
+    check_openal_context!(());
+    al::alListenerfv(ffi::AL_VELOCITY, &velocity[0]);
+}
+
+/**
+ * Get the velocity of the listener.
+ *
+ * # Return
+ * A three dimensional vector of f32 containing the velocity
+ * of the listener [x, y, z].
+ *
+ * # Example
+ * ```Rust
+ * # use ears::listener;
+ * let vel = listener::get_velocity();
+ * println!("Listener velocity: {}", &vel);
+ * ```
+ */
+pub fn get_velocity() -> [f32, ..3] {
+    check_openal_context!([0., ..3]);
+
+    let mut velocity: [f32, ..3] = [0., ..3];
+    al::alGetListenerfv(ffi::AL_VELOCITY, &mut velocity[0]);
+    velocity
+}
+
+/**
+ * Set the Doppler factor of the scene.
+ *
+ * Exaggerate or diminish the pitch shift caused by the velocities of the
+ * listener and the sources. At 0.0, the Doppler effect is disabled.
+ *
+ * Default is 1.
+ *
+ * # Argument
+ * * `factor` - The Doppler factor, in the range [0., +inf]
+ *
+ * # Example
+ * ```Rust
+ * # use ears::listener;
+ * listener::set_doppler_factor(0.5f32);
+ * ```
+ */
+pub fn set_doppler_factor(factor: f32) -> () {
+    check_openal_context!(());
+    al::alDopplerFactor(factor);
+}
+
+/**
+ * Get the Doppler factor of the scene.
+ *
+ * # Return
+ * The Doppler factor, in the range [0., +inf]
+ */
+pub fn get_doppler_factor() -> f32 {
+    check_openal_context!(1.);
+    al::alGetFloat(ffi::AL_DOPPLER_FACTOR)
+}
+
+/**
+ * Set the speed of sound of the scene.
+ *
+ * The speed is expressed in the units of the positions and the velocities
+ * per second, change it to match the scale of the scene.
+ *
+ * Default is 343.3, the speed of sound in the air in meters per second.
+ *
+ * # Argument
+ * * `speed` - The speed of sound, greater than 0.
+ *
+ * # Example
+ * ```Rust
+ * # use ears::listener;
+ * // The scene uses feet
+ * listener::set_speed_of_sound(1126f32);
+ * ```
+ */
+pub fn set_speed_of_sound(speed: f32) -> () {
+    check_openal_context!(());
+    al::alSpeedOfSound(speed);
+}
+
+/**
+ * Get the speed of sound of the scene.
+ *
+ * # Return
+ * The speed of sound in units per second.
+ */
+pub fn get_speed_of_sound() -> f32 {
+    check_openal_context!(343.3);
+    al::alGetFloat(ffi::AL_SPEED_OF_SOUND)
+}
+
+/**
+ * Set the distance model of the scene.
+ *
+ * The distance model is used by all the sources, unless they use their own
+ * model, see `set_per_source_distance_model`.
+ *
+ * Default is DistanceModel::InverseClamped.
+ *
+ * # Argument
+ * * `model` - The formula attenuating the sources with their distance
+ *
+ * # Example
+ * ```Rust
+ * # use ears::{listener, DistanceModel};
+ * listener::set_distance_model(DistanceModel::LinearClamped);
+ * ```
+ */
+pub fn set_distance_model(model: DistanceModel) -> () {
+    check_openal_context!(());
+    al::alDistanceModel(model.to_al());
+}
+
+/**
+ * Get the distance model of the scene.
+ *
+ * # Return
+ * The formula attenuating the sources with their distance.
+ */
+pub fn get_distance_model() -> DistanceModel {
+    check_openal_context!(DistanceModel::InverseClamped);
+    DistanceModel::from_al(al::alGetInteger(ffi::AL_DISTANCE_MODEL))
+        .unwrap_or(DistanceModel::InverseClamped)
+}
+
+/**
+ * Make each source use its own distance model.
+ *
+ * Needs the AL_EXT_source_distance_model extension. While enabled, every
+ * Sound and Music uses the model given to its `set_distance_model`, which is
+ * DistanceModel::InverseClamped by default, and the model of
+ * `set_distance_model` is ignored. While disabled, all the sources use the
+ * model of `set_distance_model`.
+ *
+ * Default is false.
+ *
+ * # Argument
+ * * `enabled` - True to use the model of each source, false to use the
+ * global model
+ *
+ * # Return
+ * False if the OpenAL implementation doesn't support a distance model per
+ * source, true otherwise.
+ */
+pub fn set_per_source_distance_model(enabled: bool) -> bool {
+    check_openal_context!(false);
+    distance_model::enable_source_model(enabled)
+}
+
+/**
+ * Check if each source uses its own distance model.
+ *
+ * # Return
+ * True if the sources use their own model, false if they use the global
+ * model.
+ */
+pub fn is_per_source_distance_model() -> bool {
+    check_openal_context!(false);
+    distance_model::is_source_model_enabled()
+}
+
 #[cfg(test)]
 mod test {
     use listener::{set_volume, set_position, set_orientation,
-                   get_volume, get_position, get_orientation};
+                   get_volume, get_position, get_orientation,
+                   set_velocity, get_velocity,
+                   set_doppler_factor, get_doppler_factor,
+                   set_speed_of_sound, get_speed_of_sound,
+                   set_distance_model, get_distance_model};
+    use distance_model::DistanceModel;
+    use init;
 
     #[test]
     pub fn listener_set_volume() -> () {
+        let _context = init().unwrap();
         set_volume(0.77);
         assert_eq!(get_volume(), 0.77);
     }
@@ -173,6 +370,7 @@ mod test {
 
     #[test]
     pub fn listener_set_position() -> () {
+        let _context = init().unwrap();
         set_position([50f32, 150f32, 234f32]);
         let res = get_position();
         assert_eq!([res[0], res[1], res[2]][], [50f32, 150f32, 234f32][])
@@ -180,6 +378,7 @@ mod test {
 
     #[test]
     pub fn listener_set_orientation() -> () {
+        let _context = init().unwrap();
         set_orientation([50., 150., 234.], [277., 125., 71.]);
         match get_orientation() {
             ([x1, y1, z1], [x2, y2, z2]) => {
@@ -188,5 +387,36 @@ mod test {
             }
         }
     }
-}
 
+    #[test]
+    pub fn listener_set_velocity() -> () {
+        let _context = init().unwrap();
+        set_velocity([10f32, 0f32, -5f32]);
+        let res = get_velocity();
+        assert_eq!([res[0], res[1], res[2]][], [10f32, 0f32, -5f32][])
+    }
+
+    #[test]
+    pub fn listener_set_doppler_factor() -> () {
+        let _context = init().unwrap();
+        set_doppler_factor(0.5);
+        assert_eq!(get_doppler_factor(), 0.5);
+        set_doppler_factor(1.);
+    }
+
+    #[test]
+    pub fn listener_set_speed_of_sound() -> () {
+        let _context = init().unwrap();
+        set_speed_of_sound(1126.);
+        assert_eq!(get_speed_of_sound(), 1126.);
+        set_speed_of_sound(343.3);
+    }
+
+    #[test]
+    pub fn listener_set_distance_model() -> () {
+        let _context = init().unwrap();
+        set_distance_model(DistanceModel::LinearClamped);
+        assert_eq!(get_distance_model(), DistanceModel::LinearClamped);
+        set_distance_model(DistanceModel::InverseClamped);
+    }
+}
@@ -0,0 +1,447 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2014 Jeremy Letang (letang.jeremy@gmail.com)
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+//! Mixer groups controlling the volume of several Sounds and Musics.
+//!
+//! A Sound or a Music assigned to a group, e.g. "music", "sfx" or "voice",
+//! is played at its own volume multiplied by the volume of the group. The
+//! groups are created when they are first used, with a volume of 1.
+//!
+//! The settings of the groups are kept without audio context, but the master
+//! volume belongs to the context: if no AudioContext is alive,
+//! `set_master_volume` does nothing and `get_master_volume` returns 0.
+//!
+//! # Example
+//! ```Rust
+//! use ears::{mixer, Sound, Music, AudioController};
+//!
+//! let mut msc = Music::new("path/to/my/music.ogg").unwrap();
+//! let mut snd = Sound::new("path/to/my/sound.ogg").unwrap();
+//! msc.set_group(Some("music"));
+//! snd.set_group(Some("sfx"));
+//!
+//! mixer::set_volume("music", 0.4);
+//! mixer::set_muted("sfx", true);
+//! ```
+
+use std::mem;
+use std::collections::HashMap;
+use std::sync::{StaticMutex, MUTEX_INIT};
+
+use internal::OpenAlData;
+use openal::{ffi, al};
+
+/// The settings of a group.
+struct Group {
+    volume: f32,
+    muted: bool,
+    solo: bool
+}
+
+/// A source assigned to a group.
+struct Member {
+    group: String,
+    /// The volume of the source, before the group is applied
+    volume: f32,
+    /// The source has been paused by its group
+    paused: bool
+}
+
+/// The groups and their sources.
+struct Mixer {
+    groups: HashMap<String, Group>,
+    members: HashMap<u32, Member>
+}
+
+impl Mixer {
+    /// Get the settings of a group, create it if needed.
+    fn group(&mut self, name: &str) -> &mut Group {
+        if !self.groups.contains_key(name) {
+            self.groups.insert(name.to_string(), Group { volume: 1., muted: false, solo: false });
+        }
+        self.groups.get_mut(name).unwrap()
+    }
+
+    /// Get the multiplier of the volume of the sources of a group.
+    fn factor(&self, name: &str) -> f32 {
+        let any_solo = self.groups.values().any(|g| g.solo);
+        match self.groups.get(name) {
+            Some(group) if group.muted || (any_solo && !group.solo) => 0.,
+            Some(group)                                             => group.volume,
+            None if any_solo                                        => 0.,
+            None                                                    => 1.
+        }
+    }
+
+    /// Apply the volume of the groups to all their sources.
+    fn apply(&self) -> () {
+        for (source, member) in self.members.iter() {
+            al::alSourcef(*source, ffi::AL_GAIN, member.volume * self.factor(member.group[]));
+        }
+    }
+}
+
+/// Protect the access to MIXER.
+static MIXER_LOCK: StaticMutex = MUTEX_INIT;
+/// The groups shared by the whole program.
+static mut MIXER: *mut Mixer = 0 as *mut Mixer;
+
+/// Run a function on the shared mixer.
+fn with_mixer<T>(f: |&mut Mixer| -> T) -> T {
+    let _guard = MIXER_LOCK.lock();
+    unsafe {
+        if MIXER.is_null() {
+            MIXER = mem::transmute(box Mixer {
+                groups: HashMap::new(),
+                members: HashMap::new()
+            });
+        }
+        f(&mut *MIXER)
+    }
+}
+
+/**
+ * Set the master volume.
+ *
+ * The master volume applies to every Sound and Music, it is the volume of
+ * the listener, see `listener::set_volume`.
+ *
+ * # Argument
+ * * `volume` - The master volume, should be between 0. and 1.
+ */
+pub fn set_master_volume(volume: f32) -> () {
+    check_openal_context!(());
+    al::alListenerf(ffi::AL_GAIN, volume);
+}
+
+/**
+ * Get the master volume.
+ *
+ * # Return
+ * The master volume between 0. and 1.
+ */
+pub fn get_master_volume() -> f32 {
+    check_openal_context!(0.);
+
+    let mut volume : f32 = 0.;
+    al::alGetListenerf(ffi::AL_GAIN, &mut volume);
+    volume
+}
+
+/**
+ * Set the volume of a group.
+ *
+ * # Arguments
+ * * `group` - The name of the group
+ * * `volume` - The volume of the group, should be between 0. and 1.
+ *
+ * # Example
+ * ```Rust
+ * # use ears::mixer;
+ * mixer::set_volume("music", 0.5);
+ * ```
+ */
+pub fn set_volume(group: &str, volume: f32) -> () {
+    with_mixer(|mixer| {
+        mixer.group(group).volume = volume;
+        mixer.apply();
+    })
+}
+
+/**
+ * Get the volume of a group.
+ *
+ * # Argument
+ * * `group` - The name of the group
+ *
+ * # Return
+ * The volume of the group, 1. if the group doesn't exist yet.
+ */
+pub fn get_volume(group: &str) -> f32 {
+    with_mixer(|mixer| mixer.groups.get(group).map_or(1., |g| g.volume))
+}
+
+/**
+ * Mute or unmute a group.
+ *
+ * The sources of a muted group keep playing silently.
+ *
+ * # Arguments
+ * * `group` - The name of the group
+ * * `muted` - True to mute the group, false to unmute it
+ */
+pub fn set_muted(group: &str, muted: bool) -> () {
+    with_mixer(|mixer| {
+        mixer.group(group).muted = muted;
+        mixer.apply();
+    })
+}
+
+/**
+ * Check if a group is muted.
+ *
+ * # Argument
+ * * `group` - The name of the group
+ *
+ * # Return
+ * True if the group is muted, false otherwise.
+ */
+pub fn is_muted(group: &str) -> bool {
+    with_mixer(|mixer| mixer.groups.get(group).map_or(false, |g| g.muted))
+}
+
+/**
+ * Solo or unsolo a group.
+ *
+ * While at least one group is soloed, the sources of the other groups are
+ * muted. The sources without group are not affected.
+ *
+ * # Arguments
+ * * `group` - The name of the group
+ * * `solo` - True to solo the group, false to unsolo it
+ */
+pub fn set_solo(group: &str, solo: bool) -> () {
+    with_mixer(|mixer| {
+        mixer.group(group).solo = solo;
+        mixer.apply();
+    })
+}
+
+/**
+ * Check if a group is soloed.
+ *
+ * # Argument
+ * * `group` - The name of the group
+ *
+ * # Return
+ * True if the group is soloed, false otherwise.
+ */
+pub fn is_solo(group: &str) -> bool {
+    with_mixer(|mixer| mixer.groups.get(group).map_or(false, |g| g.solo))
+}
+
+/**
+ * Pause all the playing sources of a group.
+ *
+ * # Argument
+ * * `group` - The name of the group
+ */
+pub fn pause(group: &str) -> () {
+    with_mixer(|mixer| {
+        for (source, member) in mixer.members.iter_mut() {
+            if member.group[] == group && al::alGetState(*source) == ffi::AL_PLAYING {
+                al::alSourcePause(*source);
+                member.paused = true;
+            }
+        }
+    })
+}
+
+/**
+ * Resume the sources of a group paused by `mixer::pause`.
+ *
+ * # Argument
+ * * `group` - The name of the group
+ */
+pub fn resume(group: &str) -> () {
+    with_mixer(|mixer| {
+        for (source, member) in mixer.members.iter_mut() {
+            if member.group[] == group && member.paused {
+                if al::alGetState(*source) == ffi::AL_PAUSED {
+                    al::alSourcePlay(*source);
+                }
+                member.paused = false;
+            }
+        }
+    })
+}
+
+/// Assign a source to a group, or remove it from its group.
+#[doc(hidden)]
+pub fn set_source_group(source: u32, group: Option<&str>) -> () {
+    with_mixer(|mixer| {
+        let volume = match mixer.members.remove(&source) {
+            Some(member) => member.volume,
+            None         => {
+                let mut volume = 0.;
+                al::alGetSourcef(source, ffi::AL_GAIN, &mut volume);
+                volume
+            }
+        };
+        match group {
+            Some(group) => {
+                mixer.group(group);
+                mixer.members.insert(source, Member {
+                    group: group.to_string(),
+                    volume: volume,
+                    paused: false
+                });
+            },
+            None        => {}
+        }
+        let factor = match group {
+            Some(group) => mixer.factor(group),
+            None        => 1.
+        };
+        al::alSourcef(source, ffi::AL_GAIN, volume * factor);
+    })
+}
+
+/// Get the group of a source.
+#[doc(hidden)]
+pub fn get_source_group(source: u32) -> Option<String> {
+    with_mixer(|mixer| mixer.members.get(&source).map(|member| member.group.clone()))
+}
+
+/// Set the volume of a source, the volume of its group is applied.
+#[doc(hidden)]
+pub fn set_gain(source: u32, volume: f32) -> () {
+    with_mixer(|mixer| {
+        let group = match mixer.members.get_mut(&source) {
+            Some(member) => {
+                member.volume = volume;
+                Some(member.group.clone())
+            },
+            None         => None
+        };
+        let factor = match group {
+            Some(group) => mixer.factor(group[]),
+            None        => 1.
+        };
+        al::alSourcef(source, ffi::AL_GAIN, volume * factor);
+    })
+}
+
+/// Get the volume of a source, without the volume of its group.
+#[doc(hidden)]
+pub fn get_gain(source: u32) -> f32 {
+    with_mixer(|mixer| match mixer.members.get(&source) {
+        Some(member) => member.volume,
+        None         => {
+            let mut volume = 0.;
+            al::alGetSourcef(source, ffi::AL_GAIN, &mut volume);
+            volume
+        }
+    })
+}
+
+/// Forget a source before it is deleted.
+#[doc(hidden)]
+pub fn remove_source(source: u32) -> () {
+    with_mixer(|mixer| { mixer.members.remove(&source); })
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(non_snake_case)]
+
+    use std::sync::{StaticMutex, MUTEX_INIT};
+
+    use mixer;
+    use music;
+    use music::Music;
+    use sound::Sound;
+    use states::State::{Playing, Paused};
+    use audio_controller::AudioController;
+    use openal::{ffi, al};
+
+    /// Serialize the tests checking the volume applied to the sources, a
+    /// soloed group mutes the other groups.
+    static GAIN_LOCK: StaticMutex = MUTEX_INIT;
+
+    /// Get the volume applied by OpenAL to a source.
+    fn al_gain(msc: &Music) -> f32 {
+        let mut volume = 0.;
+        al::alGetSourcef(music::get_source(msc), ffi::AL_GAIN, &mut volume);
+        volume
+    }
+
+    #[test]
+    fn mixer_group_volume_OK() -> () {
+        let _guard = GAIN_LOCK.lock();
+        let mut msc = Music::new("res/shot.wav").ok().expect("Cannot create Music");
+
+        msc.set_volume(0.5);
+        msc.set_group(Some("test_volume"));
+        mixer::set_volume("test_volume", 0.5);
+        assert_eq!(msc.get_group(), Some("test_volume".to_string()));
+        assert_eq!(msc.get_volume(), 0.5);
+        assert_eq!(al_gain(&msc), 0.25);
+        msc.set_volume(1.);
+        assert_eq!(al_gain(&msc), 0.5);
+        msc.set_group(None);
+        assert_eq!(al_gain(&msc), 1.);
+    }
+
+    #[test]
+    fn mixer_group_mute_OK() -> () {
+        let _guard = GAIN_LOCK.lock();
+        let mut msc = Music::new("res/shot.wav").ok().expect("Cannot create Music");
+
+        msc.set_group(Some("test_mute"));
+        mixer::set_muted("test_mute", true);
+        assert!(mixer::is_muted("test_mute"));
+        assert_eq!(al_gain(&msc), 0.);
+        mixer::set_muted("test_mute", false);
+        assert_eq!(al_gain(&msc), 1.);
+    }
+
+    #[test]
+    fn mixer_group_solo_OK() -> () {
+        let _guard = GAIN_LOCK.lock();
+        let mut soloed = Music::new("res/shot.wav").ok().expect("Cannot create Music");
+        let mut other = Music::new("res/shot.wav").ok().expect("Cannot create Music");
+        let mut ungrouped = Music::new("res/shot.wav").ok().expect("Cannot create Music");
+
+        soloed.set_group(Some("test_solo"));
+        other.set_group(Some("test_solo_other"));
+        ungrouped.set_volume(0.5);
+        mixer::set_solo("test_solo", true);
+        assert!(mixer::is_solo("test_solo"));
+        assert_eq!(al_gain(&soloed), 1.);
+        assert_eq!(al_gain(&other), 0.);
+        assert_eq!(al_gain(&ungrouped), 0.5);
+        mixer::set_solo("test_solo", false);
+        assert_eq!(al_gain(&other), 1.);
+    }
+
+    #[test]
+    fn mixer_getters_default_OK() -> () {
+        assert_eq!(mixer::get_volume("test_unknown"), 1.);
+        assert!(!mixer::is_muted("test_unknown"));
+        assert!(!mixer::is_solo("test_unknown"));
+        assert!(!mixer::with_mixer(|mixer| mixer.groups.contains_key("test_unknown")));
+    }
+
+    #[test]
+    fn mixer_group_pause_OK() -> () {
+        let mut snd = Sound::new("res/shot.wav").ok().expect("Cannot create sound");
+
+        snd.set_group(Some("test_pause"));
+        snd.set_looping(true);
+        snd.play();
+        mixer::pause("test_pause");
+        assert_eq!(snd.get_state() as i32, Paused as i32);
+        mixer::resume("test_pause");
+        assert_eq!(snd.get_state() as i32, Playing as i32);
+        snd.stop();
+    }
+}
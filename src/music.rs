@@ -24,19 +24,40 @@
 use std::io::timer::sleep;
 use std::mem;
 use std::task;
+use std::cmp;
 use std::time::Duration;
+use std::sync::{Arc, Mutex, Condvar};
+use std::io::MemReader;
+use std::collections::RingBuf;
 use libc::c_void;
+use time;
 use std::vec::Vec;
 
 use internal::OpenAlData;
+use context::AudioContext;
+use error::Error;
 use openal::{ffi, al};
-use sndfile::{SndInfo, SndFile};
+use sndfile::SndFile;
 use sndfile::OpenMode::Read;
-use sndfile::SeekMode::SeekSet;
 use states::State;
 use states::State::{Initial, Playing, Paused, Stopped};
 use audio_controller::AudioController;
+use fade;
+use fade::FadeCurve;
+use distance_model;
+use distance_model::DistanceModel;
+use effects;
+use effects::{EffectSlot, Filter};
+use mixer;
+use events::PlaybackEvent;
+use audio_tags;
 use audio_tags::{Tags, AudioTags, get_sound_tags};
+use sample_source::{SampleSource, FileSource};
+
+/// Default duration of the samples of a streaming buffer, in milliseconds.
+const DEFAULT_BUFFER_DURATION: i64 = 500;
+/// Default number of streaming buffers.
+const DEFAULT_BUFFER_COUNT: uint = 2;
 
 /**
  * Play Music easily.
@@ -62,20 +83,44 @@ use audio_tags::{Tags, AudioTags, get_sound_tags};
  * ```
  */
 pub struct Music {
+    /// The audio context owning the source and the buffers
+    context: AudioContext,
     /// The internal OpenAL source identifier
     al_source: u32,
     /// The internal OpenAL buffers
-    al_buffers: [u32, ..2],
-    /// The file open with libmscfile
-    file: Option<Box<SndFile>>,
-    /// Information of the file
-    file_infos: SndInfo,
-    /// Quantity of sample to read each time
-    sample_to_read: i32,
+    al_buffers: Vec<u32>,
+    /// The producer of the samples, shared with the streaming task
+    source: Arc<Mutex<Box<SampleSource + Send>>>,
+    /// The number of channels of the samples
+    channels: i32,
+    /// The samplerate of the samples
+    samplerate: i32,
+    /// The total number of frames, if known
+    frames: Option<i64>,
+    /// Duration of the samples of each buffer
+    buffer_duration: Duration,
+    /// Number of buffers queued while streaming
+    buffer_count: uint,
     /// Format of the sample
     sample_format: i32,
     /// Audio tags
-    sound_tags: Tags
+    sound_tags: Tags,
+    /// Position in frames of the first sample of the queued buffers
+    queue_offset: Arc<Mutex<i64>>,
+    /// Notified when the streaming task terminate
+    stream_end: Option<Receiver<()>>,
+    /// The loop settings, shared with the streaming task
+    loop_points: Arc<Mutex<LoopPoints>>,
+    /// Why the source is stopped, shared with the streaming task
+    stop_request: Arc<Mutex<StopRequest>>,
+    /// The channel of the events, shared with the streaming task
+    events: Arc<Mutex<Option<Sender<PlaybackEvent>>>>,
+    /// True while the streaming task runs, notified when it terminates
+    streaming: Arc<(Mutex<bool>, Condvar)>,
+    /// The effect slots connected to the auxiliary sends
+    effect_sends: Vec<(i32, EffectSlot)>,
+    /// The filter of the direct output
+    direct_filter: Option<Filter>
 }
 
 impl Music {
@@ -86,134 +131,549 @@ impl Music {
      * * `path` - The path of the file to load the music
      *
      * # Return
-     * An Option containing Some(Music) on success, None otherwise
+     * A Result containing Ok(Music) on success, Err(Error) otherwise
      */
-    pub fn new(path: &str) -> Option<Music> {
+    pub fn new(path: &str) -> Result<Music, Error> {
         // Check that OpenAL is launched
-        check_openal_context!(None);
+        let context = try!(OpenAlData::check_al_context());
         // Retrieve File and Music datas
-        let file = match SndFile::new(path, Read) {
-            Ok(file)    => box file,
-            Err(err)    => { println!("{}", err); return None; }
+        let file = try!(SndFile::new(path, Read));
+
+        Music::from_file(context, file)
+    }
+
+    /**
+     * Create a new Music reading an encoded audio file from a stream.
+     *
+     * Warning: libsndfile needs random access to the encoded data, so the
+     * whole stream is read in memory before this function returns, and stays
+     * there until the Music is dropped. Only the decoding is progressive, the
+     * encoded file costs as much memory as with `SoundData::from_bytes`. Use
+     * `Music::new` to stream a file from the disk.
+     *
+     * # Argument
+     * * `reader` - The stream containing the audio file, e.g. an entry of an
+     * archive or a network stream.
+     *
+     * # Return
+     * A Result containing Ok(Music) on success, Err(Error) otherwise
+     */
+    pub fn from_reader(mut reader: Box<Reader + Send>) -> Result<Music, Error> {
+        // Check that OpenAL is launched
+        let context = try!(OpenAlData::check_al_context());
+        // Retrieve the encoded datas then the File and Music datas
+        let bytes = match reader.read_to_end() {
+            Ok(bytes) => bytes,
+            Err(err)  => return Err(Error::IoError(err.desc.to_string()))
+        };
+        let file = try!(SndFile::new_with_reader(box MemReader::new(bytes)));
+
+        Music::from_file(context, file)
+    }
+
+    /**
+     * Create a new Music streaming the samples of a custom source.
+     *
+     * # Argument
+     * * `source` - The producer of the samples
+     *
+     * # Return
+     * A Result containing Ok(Music) on success, Err(Error) otherwise
+     */
+    pub fn from_source(source: Box<SampleSource + Send>) -> Result<Music, Error> {
+        // Check that OpenAL is launched
+        let context = try!(OpenAlData::check_al_context());
+
+        Music::from_sample_source(context, source, audio_tags::empty())
+    }
+
+    /// Stream an opened file.
+    fn from_file(context: AudioContext, file: SndFile) -> Result<Music, Error> {
+        let sound_tags = get_sound_tags(&file);
+
+        Music::from_sample_source(context, box FileSource::new(file), sound_tags)
+    }
+
+    /// Create the OpenAL source and buffers to stream a SampleSource.
+    fn from_sample_source(context: AudioContext,
+                          source: Box<SampleSource + Send>,
+                          sound_tags: Tags) -> Result<Music, Error> {
+        let channels = source.get_channels();
+
+        // Retrieve format informations
+        let format =  match al::get_channels_format(channels) {
+            Some(fmt) => fmt,
+            None      => return Err(Error::UnsupportedChannels(channels))
         };
-        let infos = file.get_sndinfo();
 
         // create the source and the buffers
         let mut source_id = 0;
-        let mut buffer_ids = [0, ..2];
+        let mut buffer_ids = Vec::from_elem(DEFAULT_BUFFER_COUNT, 0u32);
         // create the source
         al::alGenSources(1, &mut source_id);
         // create the buffers
-        al::alGenBuffers(2, &mut buffer_ids[0]);
-
-        // Retrieve format informations
-        let format =  match al::get_channels_format(infos.channels) {
-            Some(fmt) => fmt,
-            None => {
-                println!("Internal error : unrecognized format.");
-                return None;
-            }
-        };
+        al::alGenBuffers(DEFAULT_BUFFER_COUNT as i32, &mut buffer_ids[0]);
 
         // Check if there is OpenAL internal error
         match al::openal_has_error() {
-            Some(err) => { println!("{}", err); return None; },
+            Some(err) => return Err(err),
             None => {}
         };
 
-        let sound_tags = get_sound_tags(&*file);
+        // Use the loop stored with the samples, if any
+        let loop_points = match source.get_loop_points() {
+            Some((start, end)) => LoopPoints { looping: false, start: start, end: Some(end) },
+            None               => LoopPoints { looping: false, start: 0, end: None }
+        };
 
-        Some( Music {
+        Ok( Music {
+            context: context,
             al_source: source_id,
             al_buffers: buffer_ids,
-            file: Some(file),
-            file_infos: infos,
-            sample_to_read: 50000,
+            samplerate: source.get_samplerate(),
+            frames: source.get_frames(),
+            source: Arc::new(Mutex::new(source)),
+            channels: channels,
+            buffer_duration: Duration::milliseconds(DEFAULT_BUFFER_DURATION),
+            buffer_count: DEFAULT_BUFFER_COUNT,
             sample_format: format,
-            sound_tags: sound_tags
+            sound_tags: sound_tags,
+            queue_offset: Arc::new(Mutex::new(0)),
+            stream_end: None,
+            loop_points: Arc::new(Mutex::new(loop_points)),
+            stop_request: Arc::new(Mutex::new(StopRequest::Unrequested)),
+            events: Arc::new(Mutex::new(None)),
+            streaming: Arc::new((Mutex::new(false), Condvar::new())),
+            effect_sends: Vec::new(),
+            direct_filter: None
         })
     }
 
+    /**
+     * Move the playback position of the Music.
+     *
+     * If the Music is playing or paused, the queued buffers are flushed and
+     * refilled from the new position, and the Music keeps its state.
+     * Otherwise the position is used the next time the Music is played.
+     *
+     * Nothing happens if the Music streams a SampleSource which can't seek.
+     *
+     * # Argument
+     * * `offset` - The new position from the begining of the Music,
+     * clamped to the duration of the Music.
+     */
+    pub fn seek(&mut self, offset: Duration) -> () {
+        check_openal_context!(());
+
+        let frames = cmp::max(offset.num_milliseconds() * self.samplerate as i64 / 1000, 0);
+        let frames = match self.frames {
+            Some(total) => cmp::min(frames, total),
+            None        => frames
+        };
+
+        // Nothing happens if the source can't seek
+        if !self.source.lock().seek(frames) {
+            return;
+        }
+
+        let state = self.get_state();
+        match state {
+            Playing | Paused => {
+                self.stop_stream(StopRequest::Restart);
+                self.stream_from(frames);
+                if state == Paused {
+                    al::alSourcePause(self.al_source);
+                }
+            },
+            _                => {
+                self.wait_stream_end();
+                *self.queue_offset.lock() = frames;
+            }
+        }
+    }
+
+    /// Stop the source then wait for the streaming task to terminate.
+    fn stop_stream(&mut self, request: StopRequest) -> () {
+        {
+            let mut stop_request = self.stop_request.lock();
+            *stop_request = request;
+            al::alSourceStop(self.al_source);
+        }
+        self.wait_stream_end();
+    }
+
+    /// Block until the streaming task, if any, has terminated.
+    fn wait_stream_end(&mut self) -> () {
+        match self.stream_end.take() {
+            Some(end) => { let _ = end.recv_opt(); },
+            None      => {}
+        }
+    }
+
+    /**
+     * Set the duration of the samples of each streaming buffer.
+     *
+     * The buffers are filled with whole frames, so they last the same time
+     * whatever the number of channels. Longer buffers are more tolerant to a
+     * busy system, shorter ones use less memory.
+     * The new duration is used the next time the Music is played or seeked.
+     *
+     * # Argument
+     * * `duration` - The duration of each buffer, at least 1 millisecond
+     */
+    pub fn set_buffer_duration(&mut self, duration: Duration) -> () {
+        self.buffer_duration = cmp::max(duration, Duration::milliseconds(1));
+    }
+
+    /**
+     * Get the duration of the samples of each streaming buffer.
+     *
+     * # Return
+     * The duration of each buffer, 500 milliseconds by default
+     */
+    pub fn get_buffer_duration(&self) -> Duration {
+        self.buffer_duration
+    }
+
+    /**
+     * Set the number of buffers queued while streaming.
+     *
+     * More buffers make the streaming more tolerant to a busy system.
+     * The new number is used the next time the Music is played or seeked.
+     *
+     * # Argument
+     * * `count` - The number of buffers, at least 2
+     */
+    pub fn set_buffer_count(&mut self, count: uint) -> () {
+        self.buffer_count = cmp::max(count, 2);
+    }
+
+    /**
+     * Get the number of buffers queued while streaming.
+     *
+     * # Return
+     * The number of buffers, 2 by default
+     */
+    pub fn get_buffer_count(&self) -> uint {
+        self.buffer_count
+    }
+
+    /**
+     * Set the loop points of the Music.
+     *
+     * When the Music is looping, the frames before start are played once,
+     * then the frames from start to end are repeated, e.g. to loop the body
+     * of a music after its intro. The loop points are read from the
+     * instrument chunk of the file when it has one, otherwise the whole
+     * Music loops.
+     *
+     * # Arguments
+     * * `start` - The first frame of the loop
+     * * `end` - The frame following the last frame of the loop, or None to
+     * loop until the end of the Music. Ignored if not after start.
+     */
+    pub fn set_loop_points(&mut self, start: i64, end: Option<i64>) -> () {
+        let start = match self.frames {
+            Some(total) => cmp::min(cmp::max(start, 0), total),
+            None        => cmp::max(start, 0)
+        };
+        let end = match (end, self.frames) {
+            (Some(end), _) if end <= start => None,
+            (Some(end), Some(total))       => Some(cmp::min(end, total)),
+            (end, _)                       => end
+        };
+        let mut points = self.loop_points.lock();
+        points.start = start;
+        points.end = end;
+    }
+
+    /**
+     * Get the loop points of the Music.
+     *
+     * # Return
+     * The first frame of the loop, and the frame following its last frame
+     * or None if the loop goes until the end of the Music.
+     */
+    pub fn get_loop_points(&self) -> (i64, Option<i64>) {
+        let points = self.loop_points.lock();
+        (points.start, points.end)
+    }
+
+    /// Start the streaming from the given frame, if the source can seek.
+    fn stream_from(&mut self, frame: i64) -> () {
+        // No buffer is queued here, so they can be recreated
+        if self.al_buffers.len() != self.buffer_count {
+            unsafe {
+                ffi::alDeleteBuffers(self.al_buffers.len() as i32, &mut self.al_buffers[0]);
+            }
+            self.al_buffers = Vec::from_elem(self.buffer_count, 0u32);
+            al::alGenBuffers(self.buffer_count as i32, &mut self.al_buffers[0]);
+        }
+        let position = if self.source.lock().seek(frame) { frame } else { 0 };
+        *self.queue_offset.lock() = position;
+        self.process_music();
+    }
+
     fn process_music(&mut self) -> () {
-        let (chan, port) = channel();
-        let sample_t_r = self.sample_to_read;
-        let sample_rate = self.file_infos.samplerate;
-        let sample_format = self.sample_format;
+        let (end_chan, end_port) = channel();
         let al_source = self.al_source;
-        let al_buffers = self.al_buffers;
-
-        // create buff
-        let mut samples = Vec::from_elem(sample_t_r as uint, 0i16);
-
-        // full buff1
-        let mut len = mem::size_of::<i16>() *
-            self.file.as_mut().unwrap().read_i16(samples.as_mut_slice(), sample_t_r as i64) as uint;
-        al::alBufferData(al_buffers[0],
-                         sample_format,
-                         samples.as_ptr() as *mut c_void,
-                         len as i32,
-                         sample_rate);
-
-        // full buff2
-        samples.clear();
-        len = mem::size_of::<i16>() *
-            self.file.as_mut().unwrap().read_i16(samples.as_mut_slice(), sample_t_r as i64) as uint;
-        al::alBufferData(al_buffers[1],
-                         sample_format,
-                         samples.as_ptr() as *mut c_void,
-                         len as i32,
-                         sample_rate);
-
-        // Queue the buffers
-        al::alSourceQueueBuffers(al_source, 2, &al_buffers[0]);
+        let al_buffers = self.al_buffers.clone();
+        // Read whole frames, so each buffer last the same duration
+        let buffer_ms = self.buffer_duration.num_milliseconds();
+        let frames_per_buffer = cmp::max(buffer_ms * self.samplerate as i64 / 1000, 1);
+        let sample_t_r = (frames_per_buffer * self.channels as i64) as uint;
+        // Check the buffers often enough to refill them before they run out
+        let poll_delay = Duration::milliseconds(cmp::max(cmp::min(buffer_ms / 2, 50), 1));
+        let queue_offset = self.queue_offset.clone();
+        let stop_request = self.stop_request.clone();
+        let events = self.events.clone();
+        let streaming = self.streaming.clone();
+        let mut stream = Stream {
+            source: self.source.clone(),
+            loop_points: self.loop_points.clone(),
+            position: *queue_offset.lock(),
+            buffer_ends: RingBuf::new(),
+            samples: Vec::from_elem(sample_t_r, 0i16),
+            end_of_stream: false,
+            channels: self.channels,
+            sample_format: self.sample_format,
+            sample_rate: self.samplerate
+        };
+        self.stream_end = Some(end_port);
+        *stop_request.lock() = StopRequest::Unrequested;
+        {
+            let &(ref running, _) = &*streaming;
+            *running.lock() = true;
+        }
+
+        // full the buffers then queue them, keep the ones the source has no
+        // samples for yet
+        let mut empty = Vec::new();
+        for buffer in al_buffers.iter() {
+            if stream.fill(*buffer) != 0 {
+                al::alSourceQueueBuffers(al_source, 1, buffer);
+            } else {
+                empty.push(*buffer);
+            }
+        }
 
         // Launche the Music
         al::alSourcePlay(al_source);
 
         task::spawn(proc() {
-            match OpenAlData::check_al_context() {
-                Ok(_)       => {},
-                Err(err)    => { println!("{}", err);}
-            };
-
-            let mut file: Box<SndFile> = port.recv();
-            let mut samples = Vec::from_elem(sample_t_r as uint, 0i16);
-            let mut status = ffi::AL_PLAYING;
+            let mut stream = stream;
+            let mut empty = empty;
             let mut i = 0;
             let mut buf = 0;
-            let mut read;
 
-            while status != ffi::AL_STOPPED {
+            loop {
                 // wait a bit
-                sleep(Duration::milliseconds(50i64));
-                if status == ffi::AL_PLAYING {
-                    al::alGetSourcei(al_source,
-                                     ffi::AL_BUFFERS_PROCESSED,
-                                     &mut i);
-                    if i != 0 {
-                        samples.clear();
+                sleep(poll_delay);
+                // Get source status
+                let status = al::alGetState(al_source);
+                if status == ffi::AL_PAUSED {
+                    continue;
+                }
+                // Only the checks of the stop requests hold the lock, so a
+                // stop doesn't wait for the refill of the buffers
+                if status == ffi::AL_STOPPED {
+                    let request = stop_request.lock();
+                    if *request != StopRequest::Unrequested {
+                        if *request == StopRequest::Stop {
+                            send_event(&events, PlaybackEvent::Stopped);
+                        }
+                        break;
+                    }
+                }
+
+                al::alGetSourcei(al_source,
+                                 ffi::AL_BUFFERS_PROCESSED,
+                                 &mut i);
+                for _ in range(0, i) {
+                    {
+                        // Move the queue offset to the start of the next buffer
+                        let mut offset = queue_offset.lock();
                         al::alSourceUnqueueBuffers(al_source, 1, &mut buf);
-                        read = file.read_i16(samples.as_mut_slice(), sample_t_r as i64) *
-                            mem::size_of::<i16>() as i64;
-                        al::alBufferData(buf,
-                                         sample_format,
-                                         samples.as_ptr() as *mut c_void,
-                                         read as i32,
-                                         sample_rate);
-                        al::alSourceQueueBuffers(al_source, 1, &buf);
+                        match stream.buffer_ends.pop_front() {
+                            Some((end, looped)) => {
+                                *offset = end;
+                                if looped {
+                                    send_event(&events, PlaybackEvent::Looped);
+                                }
+                            },
+                            None                => {}
+                        }
                     }
+                    empty.push(buf);
+                }
+
+                // Refill the empty buffers, until a stop is requested
+                loop {
+                    if *stop_request.lock() != StopRequest::Unrequested {
+                        break;
+                    }
+                    let buffer = match empty.pop() {
+                        Some(buffer) => buffer,
+                        None         => break
+                    };
+                    if stream.fill(buffer) == 0 {
+                        // No samples available yet, retry on the next poll
+                        empty.push(buffer);
+                        break;
+                    }
+                    al::alSourceQueueBuffers(al_source, 1, &buffer);
+                }
+
+                if status == ffi::AL_STOPPED {
+                    // The source is not stopped by another task while it is
+                    // resumed, a stop requested during the refill is handled
+                    // on the next poll
+                    let request = stop_request.lock();
+                    if *request != StopRequest::Unrequested {
+                        continue;
+                    }
+                    al::alGetSourcei(al_source, ffi::AL_BUFFERS_QUEUED, &mut i);
+                    if i == 0 {
+                        if stream.end_of_stream {
+                            // All the samples have been played
+                            send_event(&events, PlaybackEvent::Stopped);
+                            break;
+                        }
+                        // Wait for the source to produce samples
+                        continue;
+                    }
+                    // The source played all its buffers before they were
+                    // refilled, resume it with the refilled ones
+                    send_event(&events, PlaybackEvent::Underrun);
+                    al::alSourcePlay(al_source);
                 }
-                // Get source status
-                status = al::alGetState(al_source);
             }
             al::alSourcei(al_source, ffi::AL_BUFFER, 0);
+            *queue_offset.lock() = 0;
+            {
+                let &(ref running, ref ended) = &*streaming;
+                *running.lock() = false;
+                ended.notify_all();
+            }
+            let _ = end_chan.send_opt(());
         });
-        let file = self.file.as_ref().unwrap().clone();
-        chan.send(file);
     }
 
 }
 
+/// Send an event on the channel of the Music, if any.
+fn send_event(events: &Arc<Mutex<Option<Sender<PlaybackEvent>>>>, event: PlaybackEvent) -> () {
+    match *events.lock() {
+        Some(ref sender) => { let _ = sender.send_opt(event); },
+        None             => {}
+    }
+}
+
+/// Why the source of a Music is stopped, shared with the streaming task.
+#[doc(hidden)]
+#[deriving(Clone, Copy, PartialEq)]
+pub enum StopRequest {
+    /// The source stopped by itself, at the end of the samples or because
+    /// the buffers ran out
+    Unrequested,
+    /// The source has been stopped, the streaming task sends a Stopped event
+    Stop,
+    /// The source has been stopped to stream from another position
+    Restart
+}
+
+/// The loop settings, shared with the streaming task.
+#[deriving(Clone, Copy)]
+struct LoopPoints {
+    /// Rewind to start when the end is reached
+    looping: bool,
+    /// First frame of the loop
+    start: i64,
+    /// Frame following the last frame of the loop, the end of the source if
+    /// None
+    end: Option<i64>
+}
+
+/// The state of a streaming task.
+struct Stream {
+    source: Arc<Mutex<Box<SampleSource + Send>>>,
+    loop_points: Arc<Mutex<LoopPoints>>,
+    /// Position in frames of the next sample read from the source
+    position: i64,
+    /// Position of the source after each queued buffer, and whether the
+    /// buffer loops back
+    buffer_ends: RingBuf<(i64, bool)>,
+    samples: Vec<i16>,
+    /// The source will not produce samples anymore, even by looping
+    end_of_stream: bool,
+    channels: i32,
+    sample_format: i32,
+    sample_rate: i32
+}
+
+impl Stream {
+    /// Fill an OpenAL buffer with the next samples of the source.
+    ///
+    /// If the Music is looping, the source is rewound to the loop start when
+    /// it reaches the loop end, in the same buffer so there is no gap.
+    ///
+    /// Return the number of samples in the buffer, 0 if the source has no
+    /// samples available yet or if its stream is over, see end_of_stream.
+    fn fill(&mut self, al_buffer: u32) -> uint {
+        let mut source = self.source.lock();
+        let channels = self.channels as uint;
+        let len = self.samples.len();
+        let mut read = 0;
+        let mut rewound = false;
+        let mut looped = false;
+        self.end_of_stream = false;
+
+        while read < len {
+            let points = *self.loop_points.lock();
+            let end = if points.looping { points.end } else { None };
+            let at_end = match end {
+                Some(end) => self.position >= end,
+                None      => false
+            };
+
+            let mut nb = 0;
+            if !at_end && !source.is_end_of_stream() {
+                let limit = match end {
+                    Some(end) => cmp::min(len, read + (end - self.position) as uint * channels),
+                    None      => len
+                };
+                nb = source.read(self.samples.slice_mut(read, limit));
+                read += nb;
+                self.position += (nb / channels) as i64;
+            }
+
+            if nb == 0 {
+                // Rewind once, an empty loop must not loop forever
+                if points.looping && !rewound && source.seek(points.start) {
+                    self.position = points.start;
+                    rewound = true;
+                    looped = true;
+                } else {
+                    self.end_of_stream = at_end || source.is_end_of_stream();
+                    break;
+                }
+            } else {
+                rewound = false;
+            }
+        }
+
+        if read != 0 {
+            al::alBufferData(al_buffer,
+                             self.sample_format,
+                             self.samples.as_ptr() as *mut c_void,
+                             (read * mem::size_of::<i16>()) as i32,
+                             self.sample_rate);
+            self.buffer_ends.push_back((self.position, looped));
+        }
+        read
+    }
+}
+
 impl AudioTags for Music {
     /**
      * Get the tags of a Sound.
@@ -236,13 +696,10 @@ impl AudioController for Music {
         match self.get_state() {
             Paused   => { al::alSourcePlay(self.al_source); return; },
             _       => {
-                if self.is_playing() {
-                    al::alSourceStop(self.al_source);
-                    // wait a bit for openal terminate
-                    sleep(Duration::milliseconds(50i64));
-                }
-                self.file.as_mut().unwrap().seek(0, SeekSet);
-                self.process_music();
+                // wait for the previous streaming task to terminate
+                self.stop_stream(StopRequest::Restart);
+                let start = *self.queue_offset.lock();
+                self.stream_from(start);
             }
         }
     }
@@ -253,7 +710,11 @@ impl AudioController for Music {
     fn pause(&mut self) -> () {
         check_openal_context!(());
 
-        al::alSourcePause(self.al_source)
+        al::alSourcePause(self.al_source);
+        // Wake up the tasks waiting for the Music to stop
+        let &(ref running, ref ended) = &*self.streaming;
+        let _running = running.lock();
+        ended.notify_all();
     }
 
     /**
@@ -262,8 +723,7 @@ impl AudioController for Music {
     fn stop(&mut self) -> () {
         check_openal_context!(());
 
-        al::alSourceStop(self.al_source);
-        sleep(Duration::milliseconds(50i64));
+        self.stop_stream(StopRequest::Stop);
     }
 
     /**
@@ -312,7 +772,7 @@ impl AudioController for Music {
     fn set_volume(&mut self, volume: f32) -> () {
         check_openal_context!(());
 
-        al::alSourcef(self.al_source, ffi::AL_GAIN, volume);
+        mixer::set_gain(self.al_source, volume);
     }
 
     /**
@@ -324,9 +784,7 @@ impl AudioController for Music {
     fn get_volume(&self) -> f32 {
         check_openal_context!(0.);
 
-        let mut volume : f32 = 0.;
-        al::alGetSourcef(self.al_source, ffi::AL_GAIN, &mut volume);
-        volume
+        mixer::get_gain(self.al_source)
     }
 
     /**
@@ -392,22 +850,17 @@ impl AudioController for Music {
     /**
      * Set the Music looping or not
      *
+     * The streaming task rewinds the Music to the loop start when it reaches
+     * the loop end, so the Music loops without gap. By default the whole
+     * Music loops, see set_loop_points.
+     *
      * The default looping is false.
      *
      * # Arguments
      * `looping` - The new looping state.
      */
     fn set_looping(&mut self, looping: bool) -> () {
-        check_openal_context!(());
-
-        match looping {
-            true    => al::alSourcei(self.al_source,
-                                     ffi::AL_LOOPING,
-                                     ffi::ALC_TRUE as i32),
-            false   => al::alSourcei(self.al_source,
-                                     ffi::AL_LOOPING,
-                                     ffi::ALC_FALSE as i32)
-        };
+        self.loop_points.lock().looping = looping;
     }
 
     /**
@@ -417,15 +870,7 @@ impl AudioController for Music {
      * True if the Music is looping, false otherwise.
      */
     fn is_looping(&self) -> bool {
-        check_openal_context!(false);
-
-        let mut boolean = 0;
-        al::alGetSourcei(self.al_source, ffi::AL_LOOPING, &mut boolean);
-        match boolean as i8 {
-            ffi::ALC_TRUE  => true,
-            ffi::ALC_FALSE => false,
-            _              => unreachable!()
-        }
+        self.loop_points.lock().looping
     }
 
     /**
@@ -662,15 +1107,425 @@ impl AudioController for Music {
                          &mut attenuation);
         attenuation
     }
+
+    /**
+     * Set the distance model of the Music.
+     *
+     * Needs the AL_EXT_source_distance_model extension, see
+     * `AudioController::set_distance_model`.
+     *
+     * # Argument
+     * * `model` - The formula attenuating the Music with its distance
+     *
+     * # Return
+     * False if the distance model per source is not supported, true otherwise.
+     */
+    fn set_distance_model(&mut self, model: DistanceModel) -> bool {
+        check_openal_context!(false);
+
+        distance_model::set_source_model(self.al_source, model)
+    }
+
+    /**
+     * Get the distance model used by the Music.
+     *
+     * # Return
+     * The model of the Music if the sources use their own model, the global
+     * model otherwise.
+     */
+    fn get_distance_model(&self) -> DistanceModel {
+        check_openal_context!(DistanceModel::InverseClamped);
+
+        distance_model::get_source_model(self.al_source)
+    }
+
+    /**
+     * Send the Music to an effect slot.
+     *
+     * # Arguments
+     * * `send` - The auxiliary send used, between 0 and
+     * `effects::get_max_sends()` excluded
+     * * `slot` - The slot receiving the samples
+     * * `gain` - The volume of the samples sent, between 0. and 1.
+     *
+     * # Return
+     * A Result containing Ok(()) on success, Err(Error) otherwise.
+     */
+    fn set_effect_send(&mut self, send: i32, slot: &EffectSlot, gain: f32) -> Result<(), Error> {
+        check_openal_context!();
+
+        try!(effects::connect_send(self.al_source, send, slot, gain));
+        self.effect_sends.retain(|&(s, _)| s != send);
+        self.effect_sends.push((send, slot.clone()));
+        Ok(())
+    }
+
+    /**
+     * Stop sending the Music to the effect slot of an auxiliary send.
+     *
+     * # Argument
+     * * `send` - The auxiliary send to disconnect
+     */
+    fn remove_effect_send(&mut self, send: i32) -> () {
+        check_openal_context!(());
+
+        if self.effect_sends.iter().any(|&(s, _)| s == send) {
+            effects::disconnect_send(self.al_source, send);
+            self.effect_sends.retain(|&(s, _)| s != send);
+        }
+    }
+
+    /**
+     * Filter the direct output of the Music.
+     *
+     * Nothing happens if the effects are not supported.
+     *
+     * # Argument
+     * * `filter` - The filter applied to the direct output
+     */
+    fn set_direct_filter(&mut self, filter: Filter) -> () {
+        check_openal_context!(());
+
+        if effects::set_direct_filter(self.al_source, Some(filter)) {
+            self.direct_filter = Some(filter);
+        }
+    }
+
+    /**
+     * Remove the filter of the direct output of the Music.
+     */
+    fn remove_direct_filter(&mut self) -> () {
+        check_openal_context!(());
+
+        if self.direct_filter.is_some() {
+            effects::set_direct_filter(self.al_source, None);
+            self.direct_filter = None;
+        }
+    }
+
+    /**
+     * Get the filter of the direct output of the Music.
+     *
+     * # Return
+     * The filter applied to the direct output, None if there is no filter
+     * or the effects are not supported.
+     */
+    fn get_direct_filter(&self) -> Option<Filter> {
+        self.direct_filter
+    }
+
+    /**
+     * Assign the Music to a mixer group.
+     *
+     * The Music is played at its volume multiplied by the volume of the
+     * group, see the `mixer` module.
+     *
+     * # Argument
+     * * `group` - The name of the group, or None to remove the Music from its
+     * group
+     */
+    fn set_group(&mut self, group: Option<&str>) -> () {
+        check_openal_context!(());
+
+        mixer::set_source_group(self.al_source, group);
+    }
+
+    /**
+     * Get the mixer group of the Music.
+     *
+     * # Return
+     * The name of the group of the Music, None if it has no group.
+     */
+    fn get_group(&self) -> Option<String> {
+        mixer::get_source_group(self.al_source)
+    }
+
+    /**
+     * Set the inner angle of the cone of the Music.
+     *
+     * Inside the cone defined by the direction of the Music and this angle,
+     * the Music is not attenuated by its orientation.
+     *
+     * The default inner angle is 360.
+     *
+     * # Argument
+     * * `angle` - The new inner angle in degrees, in the range [0., 360.]
+     */
+    fn set_cone_inner_angle(&mut self, angle: f32) -> () {
+        check_openal_context!(());
+
+        al::alSourcef(self.al_source, ffi::AL_CONE_INNER_ANGLE, angle);
+    }
+
+    /**
+     * Get the inner angle of the cone of the Music.
+     *
+     * # Return
+     * The inner angle in degrees, in the range [0., 360.]
+     */
+    fn get_cone_inner_angle(&self) -> f32 {
+        check_openal_context!(360.);
+
+        let mut angle = 0.;
+        al::alGetSourcef(self.al_source, ffi::AL_CONE_INNER_ANGLE, &mut angle);
+        angle
+    }
+
+    /**
+     * Set the outer angle of the cone of the Music.
+     *
+     * Outside the cone defined by the direction of the Music and this angle,
+     * the volume of the Music is multiplied by the outer volume. Between the
+     * inner and the outer angles, the volume is interpolated.
+     *
+     * The default outer angle is 360.
+     *
+     * # Argument
+     * * `angle` - The new outer angle in degrees, in the range [0., 360.]
+     */
+    fn set_cone_outer_angle(&mut self, angle: f32) -> () {
+        check_openal_context!(());
+
+        al::alSourcef(self.al_source, ffi::AL_CONE_OUTER_ANGLE, angle);
+    }
+
+    /**
+     * Get the outer angle of the cone of the Music.
+     *
+     * # Return
+     * The outer angle in degrees, in the range [0., 360.]
+     */
+    fn get_cone_outer_angle(&self) -> f32 {
+        check_openal_context!(360.);
+
+        let mut angle = 0.;
+        al::alGetSourcef(self.al_source, ffi::AL_CONE_OUTER_ANGLE, &mut angle);
+        angle
+    }
+
+    /**
+     * Set the volume of the Music outside its cone.
+     *
+     * The default outer volume is 0.
+     *
+     * # Argument
+     * * `volume` - The new outer volume, in the range [0., 1.]
+     */
+    fn set_cone_outer_volume(&mut self, volume: f32) -> () {
+        check_openal_context!(());
+
+        al::alSourcef(self.al_source, ffi::AL_CONE_OUTER_GAIN, volume);
+    }
+
+    /**
+     * Get the volume of the Music outside its cone.
+     *
+     * # Return
+     * The outer volume, in the range [0., 1.]
+     */
+    fn get_cone_outer_volume(&self) -> f32 {
+        check_openal_context!(0.);
+
+        let mut volume = 0.;
+        al::alGetSourcef(self.al_source, ffi::AL_CONE_OUTER_GAIN, &mut volume);
+        volume
+    }
+
+    /**
+     * Set the velocity of the Music.
+     *
+     * The velocity doesn't move the Music, it is only used to compute the
+     * Doppler effect, see `listener::set_doppler_factor`.
+     *
+     * The default velocity is [0., 0., 0.].
+     *
+     * # Argument
+     * * `velocity` - A three dimensional vector of f32 containing the
+     * velocity of the Music [x, y, z], in units per second.
+     */
+    fn set_velocity(&mut self, velocity: [f32, ..3]) -> () {
+        check_openal_context!(());
+
+        al::alSourcefv(self.al_source, ffi::AL_VELOCITY, &velocity[0]);
+    }
+
+    /**
+     * Get the velocity of the Music.
+     *
+     * # Return
+     * A three dimensional vector of f32 containing the velocity of the
+     * Music [x, y, z].
+     */
+    fn get_velocity(&self) -> [f32, ..3] {
+        check_openal_context!([0., ..3]);
+
+        let mut velocity : [f32, ..3] = [0., ..3];
+        al::alGetSourcefv(self.al_source, ffi::AL_VELOCITY, &mut velocity[0]);
+        velocity
+    }
+
+    /**
+     * Set the playback position of the Music.
+     *
+     * See `Music::seek`.
+     *
+     * # Argument
+     * * `offset` - The new position from the begining of the Music.
+     */
+    fn set_offset(&mut self, offset: Duration) -> () {
+        self.seek(offset)
+    }
+
+    /**
+     * Get the playback position of the Music.
+     *
+     * # Return
+     * The time elapsed since the begining of the Music.
+     */
+    fn get_offset(&self) -> Duration {
+        check_openal_context!(Duration::zero());
+
+        let frames = match self.get_state() {
+            Playing | Paused => {
+                let queue_offset = self.queue_offset.lock();
+                let mut sample_offset = 0;
+                al::alGetSourcei(self.al_source, ffi::AL_SAMPLE_OFFSET, &mut sample_offset);
+                let frames = *queue_offset + sample_offset as i64;
+                // The playing buffer may contain the end and the start of the loop
+                let points = *self.loop_points.lock();
+                match points.end.or(self.frames) {
+                    Some(end) if points.looping && frames >= end => {
+                        points.start + frames - end
+                    },
+                    _                                              => frames
+                }
+            },
+            _                => *self.queue_offset.lock()
+        };
+
+        Duration::milliseconds(frames * 1000 / self.samplerate as i64)
+    }
+
+    /**
+     * Get the total duration of the Music.
+     *
+     * # Return
+     * The duration of the Music, zero if the Music streams a SampleSource
+     * of unknown length.
+     */
+    fn get_duration(&self) -> Duration {
+        match self.frames {
+            Some(frames) => Duration::milliseconds(frames * 1000 /
+                                                   self.samplerate as i64),
+            None         => Duration::zero()
+        }
+    }
+
+    /**
+     * Change the volume of the Music progressively.
+     *
+     * See `AudioController::fade_to`.
+     */
+    fn fade_to(&mut self, volume: f32, duration: Duration, curve: FadeCurve) -> Receiver<()> {
+        let current = self.get_volume();
+        fade::ramp(self.al_source, ffi::AL_GAIN, current, volume, duration, curve)
+    }
+
+    /**
+     * Change the pitch of the Music progressively.
+     *
+     * See `AudioController::pitch_ramp`.
+     */
+    fn pitch_ramp(&mut self, pitch: f32, duration: Duration) -> Receiver<()> {
+        let current = self.get_pitch();
+        fade::ramp(self.al_source, ffi::AL_PITCH, current, pitch, duration, FadeCurve::Linear)
+    }
+
+    /**
+     * Get the events of the Music.
+     *
+     * The events are sent by the streaming task, the Looped events are sent
+     * once the buffer containing the loop back has been played.
+     *
+     * # Return
+     * The Receiver of the Stopped, Looped and Underrun events of the Music.
+     */
+    fn events(&mut self) -> Receiver<PlaybackEvent> {
+        let (sender, receiver) = channel();
+        *self.events.lock() = Some(sender);
+        receiver
+    }
+
+    /**
+     * Block until the Music is not playing anymore.
+     *
+     * The streaming task wakes the waiting task up when it terminates, and
+     * pause wakes it up too. A buffer underrun doesn't stop the wait.
+     */
+    fn wait_until_stopped(&self) -> () {
+        let &(ref running, ref ended) = &*self.streaming;
+        let running = running.lock();
+
+        while *running && self.get_state() != Paused {
+            ended.wait(&running);
+        }
+    }
+
+    /**
+     * Block until the Music is not playing anymore, or the timeout expires.
+     *
+     * A pause stops the wait, like for wait_until_stopped.
+     *
+     * # Argument
+     * * `timeout` - The maximum duration of the wait
+     *
+     * # Return
+     * True if the Music is not playing anymore, false if the timeout expired.
+     */
+    fn wait_timeout(&self, timeout: Duration) -> bool {
+        let &(ref running, ref ended) = &*self.streaming;
+        let running = running.lock();
+        let start = time::precise_time_ns();
+
+        while *running && self.get_state() != Paused {
+            // Only wait for the rest of the timeout after a wake up
+            let waited = Duration::nanoseconds((time::precise_time_ns() - start) as i64);
+            if waited >= timeout || !ended.wait_timeout(&running, timeout - waited) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+
+/// Get the internal OpenAL source of the Music.
+#[doc(hidden)]
+pub fn get_source(music: &Music) -> u32 {
+    music.al_source
+}
+
+/// Get the stop request shared with the streaming task of the Music.
+#[doc(hidden)]
+pub fn stop_request(music: &Music) -> Arc<Mutex<StopRequest>> {
+    music.stop_request.clone()
 }
 
+/// Get the position in frames of the first sample of the queued buffers.
+#[doc(hidden)]
+pub fn queue_offset(music: &Music) -> Arc<Mutex<i64>> {
+    music.queue_offset.clone()
+}
 
 impl Drop for Music {
     /// Destroy all the resources of the Music.
     fn drop(&mut self) -> () {
+        fade::cancel(self.al_source);
+        self.stop_stream(StopRequest::Stop);
+        mixer::remove_source(self.al_source);
         unsafe {
             al::alSourcei(self.al_source, ffi::AL_BUFFER, 0);
-            ffi::alDeleteBuffers(2, &mut self.al_buffers[0]);
+            ffi::alDeleteBuffers(self.al_buffers.len() as i32, &mut self.al_buffers[0]);
             ffi::alDeleteSources(1, &mut self.al_source);
         }
     }
@@ -680,17 +1535,74 @@ impl Drop for Music {
 mod test {
     #![allow(non_snake_case)]
 
+    use std::cmp;
+    use std::time::Duration;
+    use std::io::File;
+    use std::io::timer::sleep;
+    use std::sync::{Arc, Mutex};
+    use time;
+
     use music::Music;
     use states::State::{Playing, Paused, Stopped};
     use audio_controller::AudioController;
+    use sample_source::SampleSource;
+    use events::PlaybackEvent;
+    use effects;
+    use effects::Filter;
+
+    struct Silence {
+        channels: i32,
+        remaining: uint
+    }
+
+    impl SampleSource for Silence {
+        fn read(&mut self, buffer: &mut [i16]) -> uint {
+            let nb = if buffer.len() < self.remaining { buffer.len() } else { self.remaining };
+            for sample in buffer.slice_to_mut(nb).iter_mut() {
+                *sample = 0;
+            }
+            self.remaining -= nb;
+            nb
+        }
+
+        fn get_channels(&self) -> i32 { self.channels }
+        fn get_samplerate(&self) -> i32 { 44100 }
+        fn is_end_of_stream(&self) -> bool { self.remaining == 0 }
+    }
+
+    /// A source with no samples available every other read.
+    struct Intermittent {
+        remaining: Arc<Mutex<uint>>,
+        available: bool
+    }
+
+    impl SampleSource for Intermittent {
+        fn read(&mut self, buffer: &mut [i16]) -> uint {
+            self.available = !self.available;
+            if !self.available {
+                return 0;
+            }
+            let mut remaining = self.remaining.lock();
+            let nb = cmp::min(buffer.len(), *remaining);
+            for sample in buffer.slice_to_mut(nb).iter_mut() {
+                *sample = 0;
+            }
+            *remaining -= nb;
+            nb
+        }
+
+        fn get_channels(&self) -> i32 { 1 }
+        fn get_samplerate(&self) -> i32 { 44100 }
+        fn is_end_of_stream(&self) -> bool { *self.remaining.lock() == 0 }
+    }
 
     #[test]
     fn music_create_OK() -> () {
         let msc = Music::new("res/shot.wav");
 
         match msc {
-            Some(_) => {},
-            None    => panic!()
+            Ok(_)  => {},
+            Err(_) => panic!()
         }
     }
 
@@ -699,15 +1611,15 @@ mod test {
         let msc = Music::new("toto.wav");
 
         match msc {
-            Some(_) => panic!(),
-            None    => {}
+            Ok(_)  => panic!(),
+            Err(_) => {}
         }
     }
 
     #[test]
     #[ignore]
     fn music_play_OK() -> () {
-        let mut msc = Music::new("res/shot.wav").expect("Cannot create Music");
+        let mut msc = Music::new("res/shot.wav").ok().expect("Cannot create Music");
 
         msc.play();
         assert_eq!(msc.get_state() as i32, Playing as i32);
@@ -717,7 +1629,7 @@ mod test {
     #[test]
     #[ignore]
     fn music_pause_OK() -> () {
-        let mut msc = Music::new("res/shot.wav").expect("Cannot create Music");
+        let mut msc = Music::new("res/shot.wav").ok().expect("Cannot create Music");
 
         msc.play();
         msc.pause();
@@ -727,7 +1639,7 @@ mod test {
 
     #[test]
     fn music_stop_OK() -> () {
-        let mut msc = Music::new("res/shot.wav").expect("Cannot create Music");
+        let mut msc = Music::new("res/shot.wav").ok().expect("Cannot create Music");
 
         msc.play();
         msc.stop();
@@ -739,7 +1651,7 @@ mod test {
     #[test]
     #[ignore]
     fn music_is_playing_TRUE() -> () {
-        let mut msc = Music::new("res/shot.wav").expect("Cannot create Music");
+        let mut msc = Music::new("res/shot.wav").ok().expect("Cannot create Music");
 
         msc.play();
         assert_eq!(msc.is_playing(), true);
@@ -749,7 +1661,7 @@ mod test {
     #[test]
     #[ignore]
     fn music_is_playing_FALSE() -> () {
-        let mut msc = Music::new("res/shot.wav").expect("Cannot create Music");
+        let mut msc = Music::new("res/shot.wav").ok().expect("Cannot create Music");
 
         assert_eq!(msc.is_playing(), false);
         msc.stop();
@@ -757,7 +1669,7 @@ mod test {
 
     #[test]
     fn music_set_volume_OK() -> () {
-        let mut msc = Music::new("res/shot.wav").expect("Cannot create Music");
+        let mut msc = Music::new("res/shot.wav").ok().expect("Cannot create Music");
 
         msc.set_volume(0.7);
         assert_eq!(msc.get_volume(), 0.7);
@@ -765,7 +1677,7 @@ mod test {
 
     #[test]
     fn music_set_min_volume_OK() -> () {
-        let mut msc = Music::new("res/shot.wav").expect("Cannot create Music");
+        let mut msc = Music::new("res/shot.wav").ok().expect("Cannot create Music");
 
         msc.set_min_volume(0.1);
         assert_eq!(msc.get_min_volume(), 0.1);
@@ -773,7 +1685,7 @@ mod test {
 
     #[test]
     fn music_set_max_volume_OK() -> () {
-        let mut msc = Music::new("res/shot.wav").expect("Cannot create Music");
+        let mut msc = Music::new("res/shot.wav").ok().expect("Cannot create Music");
 
         msc.set_max_volume(0.9);
         assert_eq!(msc.get_max_volume(), 0.9);
@@ -781,7 +1693,7 @@ mod test {
 
     #[test]
     fn music_is_looping_TRUE() -> () {
-        let mut msc = Music::new("res/shot.wav").expect("Cannot create Music");
+        let mut msc = Music::new("res/shot.wav").ok().expect("Cannot create Music");
 
         msc.set_looping(true);
         assert_eq!(msc.is_looping(), true);
@@ -789,7 +1701,7 @@ mod test {
 
     #[test]
     fn music_is_looping_FALSE() -> () {
-        let mut msc = Music::new("res/shot.wav").expect("Cannot create Music");
+        let mut msc = Music::new("res/shot.wav").ok().expect("Cannot create Music");
 
         msc.set_looping(false);
         assert_eq!(msc.is_looping(), false);
@@ -797,7 +1709,7 @@ mod test {
 
     #[test]
     fn music_set_pitch_OK() -> () {
-        let mut msc = Music::new("res/shot.wav").expect("Cannot create Music");
+        let mut msc = Music::new("res/shot.wav").ok().expect("Cannot create Music");
 
         msc.set_pitch(1.5);
         assert_eq!(msc.get_pitch(), 1.5);
@@ -805,7 +1717,7 @@ mod test {
 
      #[test]
     fn music_set_relative_TRUE() -> () {
-        let mut msc = Music::new("res/shot.wav").expect("Cannot create Music");
+        let mut msc = Music::new("res/shot.wav").ok().expect("Cannot create Music");
 
         msc.set_relative(true);
         assert_eq!(msc.is_relative(), true);
@@ -813,7 +1725,7 @@ mod test {
 
     #[test]
     fn music_set_relative_FALSE() -> () {
-        let mut msc = Music::new("res/shot.wav").expect("Cannot create Music");
+        let mut msc = Music::new("res/shot.wav").ok().expect("Cannot create Music");
 
         msc.set_relative(false);
         assert_eq!(msc.is_relative(), false);
@@ -823,7 +1735,7 @@ mod test {
 
     #[test]
     fn music_set_position_OK() -> () {
-        let mut msc = Music::new("res/shot.wav").expect("Cannot create Music");
+        let mut msc = Music::new("res/shot.wav").ok().expect("Cannot create Music");
 
         msc.set_position([50., 150., 250.]);
         let res = msc.get_position();
@@ -832,7 +1744,7 @@ mod test {
 
     #[test]
     fn music_set_direction_OK() -> () {
-        let mut msc = Music::new("res/shot.wav").expect("Cannot create Music");
+        let mut msc = Music::new("res/shot.wav").ok().expect("Cannot create Music");
 
         msc.set_direction([50., 150., 250.]);
         let res = msc.get_direction();
@@ -841,7 +1753,7 @@ mod test {
 
     #[test]
     fn music_set_max_distance() -> () {
-        let mut msc = Music::new("res/shot.wav").expect("Cannot create Music");
+        let mut msc = Music::new("res/shot.wav").ok().expect("Cannot create Music");
 
         msc.set_max_distance(70.);
         assert_eq!(msc.get_max_distance(), 70.);
@@ -849,7 +1761,7 @@ mod test {
 
     #[test]
     fn music_set_reference_distance() -> () {
-        let mut msc = Music::new("res/shot.wav").expect("Cannot create Music");
+        let mut msc = Music::new("res/shot.wav").ok().expect("Cannot create Music");
 
         msc.set_reference_distance(70.);
         assert_eq!(msc.get_reference_distance(), 70.);
@@ -857,10 +1769,251 @@ mod test {
 
     #[test]
     fn music_set_attenuation() -> () {
-        let mut msc = Music::new("res/shot.wav").expect("Cannot create Music");
+        let mut msc = Music::new("res/shot.wav").ok().expect("Cannot create Music");
 
         msc.set_attenuation(0.5f32);
-        println!("{}", &msc.get_attenuation());
         assert_eq!(&msc.get_attenuation(), &0.5f32);
     }
+
+    #[test]
+    fn music_get_duration_OK() -> () {
+        let msc = Music::new("res/shot.wav").ok().expect("Cannot create Music");
+
+        assert_eq!(msc.get_duration(), Duration::milliseconds(511));
+    }
+
+    #[test]
+    fn music_seek_OK() -> () {
+        let mut msc = Music::new("res/shot.wav").ok().expect("Cannot create Music");
+
+        msc.seek(Duration::milliseconds(500));
+        assert_eq!(msc.get_offset(), Duration::milliseconds(500));
+    }
+
+    #[test]
+    fn music_seek_past_end_OK() -> () {
+        let mut msc = Music::new("res/shot.wav").ok().expect("Cannot create Music");
+
+        msc.seek(Duration::seconds(10));
+        assert_eq!(msc.get_offset(), msc.get_duration());
+    }
+
+    #[test]
+    fn music_from_reader_OK() -> () {
+        let file = File::open(&Path::new("res/shot.wav")).unwrap();
+
+        assert!(Music::from_reader(box file).is_ok());
+    }
+
+    #[test]
+    fn music_from_source_OK() -> () {
+        let msc = Music::from_source(box Silence { channels: 2, remaining: 88200 });
+
+        match msc {
+            Ok(msc) => assert_eq!(msc.get_duration(), Duration::zero()),
+            Err(_)  => panic!()
+        }
+    }
+
+    #[test]
+    fn music_from_source_play_OK() -> () {
+        let mut msc = Music::from_source(box Silence { channels: 2, remaining: 88200 })
+            .ok().expect("Cannot create Music");
+
+        msc.play();
+        assert_eq!(msc.get_state() as i32, Playing as i32);
+        msc.stop();
+    }
+
+    #[test]
+    fn music_from_source_no_samples_yet_OK() -> () {
+        let remaining = Arc::new(Mutex::new(4410u));
+        let source = box Intermittent { remaining: remaining.clone(), available: false };
+        let mut msc = Music::from_source(source).ok().expect("Cannot create Music");
+
+        msc.set_buffer_duration(Duration::milliseconds(10));
+        msc.play();
+        msc.wait_until_stopped();
+        assert_eq!(*remaining.lock(), 0);
+    }
+
+    #[test]
+    fn music_from_source_surround_play_OK() -> () {
+        let mut msc = Music::from_source(box Silence { channels: 6, remaining: 264600 })
+            .ok().expect("Cannot create Music");
+
+        msc.play();
+        assert_eq!(msc.get_state() as i32, Playing as i32);
+        msc.stop();
+    }
+
+    #[test]
+    fn music_buffer_settings_OK() -> () {
+        let mut msc = Music::new("res/shot.wav").ok().expect("Cannot create Music");
+
+        msc.set_buffer_duration(Duration::milliseconds(100));
+        msc.set_buffer_count(4);
+        assert_eq!(msc.get_buffer_duration(), Duration::milliseconds(100));
+        assert_eq!(msc.get_buffer_count(), 4);
+        msc.play();
+        assert_eq!(msc.get_state() as i32, Playing as i32);
+        msc.stop();
+    }
+
+    #[test]
+    fn music_buffer_count_min_OK() -> () {
+        let mut msc = Music::new("res/shot.wav").ok().expect("Cannot create Music");
+
+        msc.set_buffer_count(1);
+        assert_eq!(msc.get_buffer_count(), 2);
+    }
+
+    #[test]
+    fn music_set_loop_points_OK() -> () {
+        let mut msc = Music::new("res/shot.wav").ok().expect("Cannot create Music");
+
+        assert_eq!(msc.get_loop_points(), (0, None));
+        msc.set_loop_points(1000, Some(3000));
+        assert_eq!(msc.get_loop_points(), (1000, Some(3000)));
+        msc.set_loop_points(1000, Some(500));
+        assert_eq!(msc.get_loop_points(), (1000, None));
+        msc.set_loop_points(-5, Some(100000));
+        assert_eq!(msc.get_loop_points(), (0, Some(3605)));
+    }
+
+    #[test]
+    fn music_looping_stream_OK() -> () {
+        let mut msc = Music::new("res/shot.wav").ok().expect("Cannot create Music");
+
+        msc.set_buffer_duration(Duration::milliseconds(100));
+        msc.set_loop_points(1000, Some(3000));
+        msc.set_looping(true);
+        msc.play();
+        sleep(Duration::milliseconds(800));
+        assert_eq!(msc.get_state() as i32, Playing as i32);
+        let offset = msc.get_offset();
+        assert!(offset <= msc.get_duration());
+        msc.stop();
+    }
+
+    #[test]
+    fn music_pitch_ramp_OK() -> () {
+        let mut msc = Music::new("res/shot.wav").ok().expect("Cannot create Music");
+
+        msc.set_pitch(1.);
+        let done = msc.pitch_ramp(1.5, Duration::milliseconds(50));
+        assert!(done.recv_opt().is_ok());
+        assert_eq!(msc.get_pitch(), 1.5);
+    }
+
+    #[test]
+    fn music_events_stopped_OK() -> () {
+        let mut msc = Music::new("res/shot.wav").ok().expect("Cannot create Music");
+
+        let events = msc.events();
+        msc.play();
+        assert_eq!(events.recv(), PlaybackEvent::Stopped);
+        msc.play();
+        msc.seek(Duration::milliseconds(10));
+        msc.stop();
+        assert_eq!(events.recv(), PlaybackEvent::Stopped);
+        assert!(events.try_recv().is_err());
+    }
+
+    #[test]
+    fn music_events_looped_OK() -> () {
+        let mut msc = Music::new("res/shot.wav").ok().expect("Cannot create Music");
+
+        msc.set_buffer_duration(Duration::milliseconds(50));
+        msc.set_loop_points(0, Some(2000));
+        msc.set_looping(true);
+        let events = msc.events();
+        msc.play();
+        loop {
+            match events.recv() {
+                PlaybackEvent::Looped   => break,
+                PlaybackEvent::Underrun => {},
+                PlaybackEvent::Stopped  => panic!()
+            }
+        }
+        msc.stop();
+        loop {
+            match events.recv() {
+                PlaybackEvent::Stopped => break,
+                _                      => {}
+            }
+        }
+    }
+
+    #[test]
+    fn music_wait_until_stopped_OK() -> () {
+        let mut msc = Music::new("res/shot.wav").ok().expect("Cannot create Music");
+
+        msc.play();
+        msc.wait_until_stopped();
+        assert_eq!(msc.get_state() as i32, Stopped as i32);
+    }
+
+    #[test]
+    fn music_wait_timeout_OK() -> () {
+        let mut msc = Music::new("res/shot.wav").ok().expect("Cannot create Music");
+
+        msc.set_looping(true);
+        msc.play();
+        assert_eq!(msc.wait_timeout(Duration::milliseconds(50)), false);
+        msc.set_looping(false);
+        assert_eq!(msc.wait_timeout(Duration::seconds(5)), true);
+        msc.pause();
+        assert_eq!(msc.wait_timeout(Duration::zero()), true);
+    }
+
+    #[test]
+    fn music_wait_timeout_deadline_OK() -> () {
+        let mut msc = Music::new("res/shot.wav").ok().expect("Cannot create Music");
+
+        msc.set_looping(true);
+        msc.play();
+        let start = time::precise_time_ns();
+        assert_eq!(msc.wait_timeout(Duration::milliseconds(100)), false);
+        let waited = Duration::nanoseconds((time::precise_time_ns() - start) as i64);
+        assert!(waited >= Duration::milliseconds(100));
+        assert!(waited < Duration::milliseconds(500));
+        msc.stop();
+    }
+
+    #[test]
+    fn music_set_cone_OK() -> () {
+        let mut msc = Music::new("res/shot.wav").ok().expect("Cannot create Music");
+
+        msc.set_cone_inner_angle(45.);
+        msc.set_cone_outer_angle(90.);
+        msc.set_cone_outer_volume(0.25);
+        assert_eq!(msc.get_cone_inner_angle(), 45.);
+        assert_eq!(msc.get_cone_outer_angle(), 90.);
+        assert_eq!(msc.get_cone_outer_volume(), 0.25);
+    }
+
+    #[test]
+    fn music_set_velocity_OK() -> () {
+        let mut msc = Music::new("res/shot.wav").ok().expect("Cannot create Music");
+
+        msc.set_velocity([10f32, 0f32, -5f32]);
+        let res = msc.get_velocity();
+        assert_eq!([res[0], res[1], res[2]][], [10f32, 0f32, -5f32][]);
+    }
+
+    #[test]
+    fn music_set_direct_filter_OK() -> () {
+        let mut msc = Music::new("res/shot.wav").ok().expect("Cannot create Music");
+        let filter = Filter::LowPass { gain: 1., gain_hf: 0.2 };
+
+        msc.set_direct_filter(filter);
+        if effects::is_supported() {
+            assert_eq!(msc.get_direct_filter(), Some(filter));
+        } else {
+            assert_eq!(msc.get_direct_filter(), None);
+        }
+        msc.remove_direct_filter();
+        assert_eq!(msc.get_direct_filter(), None);
+    }
 }
@@ -51,6 +51,27 @@ pub mod ffi {
     pub const AL_MAX_DISTANCE:        i32         = 0x1023;
     pub const AL_REFERENCE_DISTANCE:  i32         = 0x1020;
     pub const AL_ROLLOFF_FACTOR:      i32         = 0x1021;
+    pub const AL_CONE_INNER_ANGLE:    i32         = 0x1001;
+    pub const AL_CONE_OUTER_ANGLE:    i32         = 0x1002;
+    pub const AL_CONE_OUTER_GAIN:     i32         = 0x1022;
+    pub const AL_VELOCITY:            i32         = 0x1006;
+
+    /// Global state
+    pub const AL_DOPPLER_FACTOR:      i32         = 0xC000;
+    pub const AL_SPEED_OF_SOUND:      i32         = 0xC003;
+
+    /// Distance models
+    pub const AL_DISTANCE_MODEL:              i32 = 0xD000;
+    pub const AL_NONE:                        i32 = 0;
+    pub const AL_INVERSE_DISTANCE:            i32 = 0xD001;
+    pub const AL_INVERSE_DISTANCE_CLAMPED:    i32 = 0xD002;
+    pub const AL_LINEAR_DISTANCE:             i32 = 0xD003;
+    pub const AL_LINEAR_DISTANCE_CLAMPED:     i32 = 0xD004;
+    pub const AL_EXPONENT_DISTANCE:           i32 = 0xD005;
+    pub const AL_EXPONENT_DISTANCE_CLAMPED:   i32 = 0xD006;
+
+    /// AL_EXT_source_distance_model
+    pub const AL_SOURCE_DISTANCE_MODEL:       i32 = 0x200;
 
     /// Sound format
     pub const AL_FORMAT_MONO16:       i32         = 0x1101;
@@ -64,6 +85,8 @@ pub mod ffi {
     pub const AL_BUFFER:              i32         = 0x1009;
     pub const AL_BUFFERS_PROCESSED:   i32         = 0x1016;
     pub const AL_BUFFERS_QUEUED:      i32         = 0x1015;
+    pub const AL_SEC_OFFSET:          i32         = 0x1024;
+    pub const AL_SAMPLE_OFFSET:       i32         = 0x1025;
 
     /// Error identifiers
     pub const AL_NO_ERROR:            i32         = 0;
@@ -82,6 +105,128 @@ pub mod ffi {
 
     /// ALC
     pub const ALC_CAPTURE_SAMPLES :    i32         = 0x312;
+    pub const ALC_FREQUENCY:                     i32 = 0x1007;
+    pub const ALC_DEFAULT_DEVICE_SPECIFIER:      i32 = 0x1004;
+    pub const ALC_DEVICE_SPECIFIER:              i32 = 0x1005;
+    pub const ALC_DEFAULT_ALL_DEVICES_SPECIFIER: i32 = 0x1012;
+    pub const ALC_ALL_DEVICES_SPECIFIER:         i32 = 0x1013;
+    pub const ALC_CAPTURE_DEVICE_SPECIFIER:         i32 = 0x310;
+    pub const ALC_CAPTURE_DEFAULT_DEVICE_SPECIFIER: i32 = 0x311;
+
+    /// ALC_SOFT_loopback
+    pub const ALC_FORMAT_CHANNELS_SOFT:          i32 = 0x1990;
+    pub const ALC_FORMAT_TYPE_SOFT:              i32 = 0x1991;
+    pub const ALC_STEREO_SOFT:                   i32 = 0x1501;
+    pub const ALC_SHORT_SOFT:                    i32 = 0x1402;
+
+    /// ALC_EXT_EFX
+    pub const ALC_MAX_AUXILIARY_SENDS:           i32 = 0x20003;
+    pub const AL_DIRECT_FILTER:                  i32 = 0x20005;
+    pub const AL_AUXILIARY_SEND_FILTER:          i32 = 0x20006;
+
+    pub const AL_EFFECT_TYPE:                    i32 = 0x8001;
+    pub const AL_EFFECT_NULL:                    i32 = 0x0000;
+    pub const AL_EFFECT_REVERB:                  i32 = 0x0001;
+    pub const AL_EFFECT_CHORUS:                  i32 = 0x0002;
+    pub const AL_EFFECT_DISTORTION:              i32 = 0x0003;
+    pub const AL_EFFECT_ECHO:                    i32 = 0x0004;
+    pub const AL_EFFECT_EQUALIZER:               i32 = 0x000C;
+
+    pub const AL_REVERB_DENSITY:                 i32 = 0x0001;
+    pub const AL_REVERB_DIFFUSION:               i32 = 0x0002;
+    pub const AL_REVERB_GAIN:                    i32 = 0x0003;
+    pub const AL_REVERB_GAINHF:                  i32 = 0x0004;
+    pub const AL_REVERB_DECAY_TIME:              i32 = 0x0005;
+    pub const AL_REVERB_DECAY_HFRATIO:           i32 = 0x0006;
+    pub const AL_REVERB_REFLECTIONS_GAIN:        i32 = 0x0007;
+    pub const AL_REVERB_REFLECTIONS_DELAY:       i32 = 0x0008;
+    pub const AL_REVERB_LATE_REVERB_GAIN:        i32 = 0x0009;
+    pub const AL_REVERB_LATE_REVERB_DELAY:       i32 = 0x000A;
+    pub const AL_REVERB_AIR_ABSORPTION_GAINHF:   i32 = 0x000B;
+    pub const AL_REVERB_ROOM_ROLLOFF_FACTOR:     i32 = 0x000C;
+    pub const AL_REVERB_DECAY_HFLIMIT:           i32 = 0x000D;
+
+    pub const AL_CHORUS_WAVEFORM:                i32 = 0x0001;
+    pub const AL_CHORUS_PHASE:                   i32 = 0x0002;
+    pub const AL_CHORUS_RATE:                    i32 = 0x0003;
+    pub const AL_CHORUS_DEPTH:                   i32 = 0x0004;
+    pub const AL_CHORUS_FEEDBACK:                i32 = 0x0005;
+    pub const AL_CHORUS_DELAY:                   i32 = 0x0006;
+
+    pub const AL_DISTORTION_EDGE:                i32 = 0x0001;
+    pub const AL_DISTORTION_GAIN:                i32 = 0x0002;
+    pub const AL_DISTORTION_LOWPASS_CUTOFF:      i32 = 0x0003;
+    pub const AL_DISTORTION_EQCENTER:            i32 = 0x0004;
+    pub const AL_DISTORTION_EQBANDWIDTH:         i32 = 0x0005;
+
+    pub const AL_ECHO_DELAY:                     i32 = 0x0001;
+    pub const AL_ECHO_LRDELAY:                   i32 = 0x0002;
+    pub const AL_ECHO_DAMPING:                   i32 = 0x0003;
+    pub const AL_ECHO_FEEDBACK:                  i32 = 0x0004;
+    pub const AL_ECHO_SPREAD:                    i32 = 0x0005;
+
+    pub const AL_EQUALIZER_LOW_GAIN:             i32 = 0x0001;
+    pub const AL_EQUALIZER_LOW_CUTOFF:           i32 = 0x0002;
+    pub const AL_EQUALIZER_MID1_GAIN:            i32 = 0x0003;
+    pub const AL_EQUALIZER_MID1_CENTER:          i32 = 0x0004;
+    pub const AL_EQUALIZER_MID1_WIDTH:           i32 = 0x0005;
+    pub const AL_EQUALIZER_MID2_GAIN:            i32 = 0x0006;
+    pub const AL_EQUALIZER_MID2_CENTER:          i32 = 0x0007;
+    pub const AL_EQUALIZER_MID2_WIDTH:           i32 = 0x0008;
+    pub const AL_EQUALIZER_HIGH_GAIN:            i32 = 0x0009;
+    pub const AL_EQUALIZER_HIGH_CUTOFF:          i32 = 0x000A;
+
+    pub const AL_FILTER_TYPE:                    i32 = 0x8001;
+    pub const AL_FILTER_NULL:                    i32 = 0x0000;
+    pub const AL_FILTER_LOWPASS:                 i32 = 0x0001;
+    pub const AL_FILTER_HIGHPASS:                i32 = 0x0002;
+    pub const AL_FILTER_BANDPASS:                i32 = 0x0003;
+    pub const AL_LOWPASS_GAIN:                   i32 = 0x0001;
+    pub const AL_LOWPASS_GAINHF:                 i32 = 0x0002;
+    pub const AL_HIGHPASS_GAIN:                  i32 = 0x0001;
+    pub const AL_HIGHPASS_GAINLF:                i32 = 0x0002;
+    pub const AL_BANDPASS_GAIN:                  i32 = 0x0001;
+    pub const AL_BANDPASS_GAINLF:                i32 = 0x0002;
+    pub const AL_BANDPASS_GAINHF:                i32 = 0x0003;
+
+    pub const AL_EFFECTSLOT_NULL:                i32 = 0x0000;
+    pub const AL_EFFECTSLOT_EFFECT:              i32 = 0x0001;
+    pub const AL_EFFECTSLOT_GAIN:                i32 = 0x0002;
+
+    pub type LPALGENEFFECTS = extern "C" fn(n: i32, effects: *mut u32);
+    pub type LPALDELETEEFFECTS = extern "C" fn(n: i32, effects: *const u32);
+    pub type LPALEFFECTI = extern "C" fn(effect: u32, param: i32, value: i32);
+    pub type LPALEFFECTF = extern "C" fn(effect: u32, param: i32, value: f32);
+    pub type LPALGENFILTERS = extern "C" fn(n: i32, filters: *mut u32);
+    pub type LPALDELETEFILTERS = extern "C" fn(n: i32, filters: *const u32);
+    pub type LPALFILTERI = extern "C" fn(filter: u32, param: i32, value: i32);
+    pub type LPALFILTERF = extern "C" fn(filter: u32, param: i32, value: f32);
+    pub type LPALGENAUXILIARYEFFECTSLOTS = extern "C" fn(n: i32, slots: *mut u32);
+    pub type LPALDELETEAUXILIARYEFFECTSLOTS = extern "C" fn(n: i32, slots: *const u32);
+    pub type LPALAUXILIARYEFFECTSLOTI = extern "C" fn(slot: u32, param: i32, value: i32);
+    pub type LPALAUXILIARYEFFECTSLOTF = extern "C" fn(slot: u32, param: i32, value: f32);
+    pub type LPALGETAUXILIARYEFFECTSLOTF = extern "C" fn(slot: u32, param: i32, value: *mut f32);
+
+    /// The functions of the EFX extension, loaded at runtime.
+    #[deriving(Copy)]
+    pub struct Efx {
+        pub alGenEffects: LPALGENEFFECTS,
+        pub alDeleteEffects: LPALDELETEEFFECTS,
+        pub alEffecti: LPALEFFECTI,
+        pub alEffectf: LPALEFFECTF,
+        pub alGenFilters: LPALGENFILTERS,
+        pub alDeleteFilters: LPALDELETEFILTERS,
+        pub alFilteri: LPALFILTERI,
+        pub alFilterf: LPALFILTERF,
+        pub alGenAuxiliaryEffectSlots: LPALGENAUXILIARYEFFECTSLOTS,
+        pub alDeleteAuxiliaryEffectSlots: LPALDELETEAUXILIARYEFFECTSLOTS,
+        pub alAuxiliaryEffectSloti: LPALAUXILIARYEFFECTSLOTI,
+        pub alAuxiliaryEffectSlotf: LPALAUXILIARYEFFECTSLOTF,
+        pub alGetAuxiliaryEffectSlotf: LPALGETAUXILIARYEFFECTSLOTF
+    }
+
+    pub type LPALCLOOPBACKOPENDEVICESOFT = extern "C" fn(devicename: *const c_char) -> *mut ALCdevice;
+    pub type LPALCRENDERSAMPLESSOFT = extern "C" fn(device: *mut ALCdevice, buffer: *mut c_void, samples: i32);
 
 
     extern "C" {
@@ -94,6 +239,7 @@ pub mod ffi {
         /// Device functions
         pub fn alcOpenDevice(devicename: *mut c_char) -> *mut ALCdevice;
         pub fn alcCloseDevice(device: *mut ALCdevice) -> ALCboolean;
+        pub fn alcGetString(device: *mut ALCdevice, param: i32) -> *const c_char;
 
         /// Listener functions
         pub fn alListenerf(param: i32, value: f32) -> ();
@@ -105,6 +251,7 @@ pub mod ffi {
 
         /// Sources functions
         pub fn alGenSources(n: i32, sources: *mut u32) -> ();
+        pub fn alIsSource(source: u32) -> ALCboolean;
         pub fn alDeleteSources(n: i32, buffers: *mut u32) -> ();
         pub fn alSourcei(source: u32, param: i32, value: i32) -> ();
         pub fn alSourcef(source: u32, param: i32, value: f32) -> ();
@@ -128,6 +275,7 @@ pub mod ffi {
 
         /// extension check
         pub fn alcIsExtensionPresent(device: *mut ALCdevice, extension: *const c_char) -> ALCboolean;
+        pub fn alcGetProcAddress(device: *mut ALCdevice, funcname: *const c_char) -> *mut c_void;
 
         /// Buffers functions
         pub fn alGenBuffers(n: i32, buffers: *mut u32) -> ();
@@ -136,6 +284,19 @@ pub mod ffi {
 
         /// Error
         pub fn alGetError() -> i32;
+
+        /// Global state
+        pub fn alDopplerFactor(value: f32) -> ();
+        pub fn alSpeedOfSound(value: f32) -> ();
+        pub fn alGetFloat(param: i32) -> f32;
+        pub fn alGetInteger(param: i32) -> i32;
+        pub fn alDistanceModel(model: i32) -> ();
+        pub fn alEnable(capability: i32) -> ();
+        pub fn alDisable(capability: i32) -> ();
+        pub fn alIsEnabled(capability: i32) -> ALCboolean;
+        pub fn alIsExtensionPresent(extension: *const c_char) -> ALCboolean;
+        pub fn alGetProcAddress(funcname: *const c_char) -> *mut c_void;
+        pub fn alSource3i(source: u32, param: i32, value1: i32, value2: i32, value3: i32) -> ();
     }
 
     #[repr(C)]
@@ -146,8 +307,11 @@ pub mod ffi {
 
 pub mod al {
 
+    use std::mem;
+    use std::c_str::CString;
     use super::ffi;
     use libc::c_void;
+    use error::{Error, OpenAlError};
 
     pub fn alBufferData(buffer: u32, format: i32, data: *mut c_void, size: i32, freq: i32) -> () {
         unsafe { ffi::alBufferData(buffer, format, data, size, freq); }
@@ -191,6 +355,10 @@ pub mod al {
         unsafe { ffi::alSourceStop(source); }
     }
 
+    pub fn alIsSource(source: u32) -> bool {
+        unsafe { ffi::alIsSource(source) == ffi::ALC_TRUE }
+    }
+
     pub fn alSourceUnqueueBuffers(source: u32, nb: i32, buffers: *mut u32) -> () {
         unsafe { ffi::alSourceUnqueueBuffers(source, nb, buffers); }
     }
@@ -235,26 +403,152 @@ pub mod al {
         unsafe { ffi::alGetListenerfv(param, values); }
     }
 
-    pub fn openal_has_error() -> Option<String> {
+    pub fn alDopplerFactor(value: f32) -> () {
+        unsafe { ffi::alDopplerFactor(value); }
+    }
+
+    pub fn alSpeedOfSound(value: f32) -> () {
+        unsafe { ffi::alSpeedOfSound(value); }
+    }
+
+    pub fn alGetFloat(param: i32) -> f32 {
+        unsafe { ffi::alGetFloat(param) }
+    }
+
+    pub fn alGetInteger(param: i32) -> i32 {
+        unsafe { ffi::alGetInteger(param) }
+    }
+
+    pub fn alDistanceModel(model: i32) -> () {
+        unsafe { ffi::alDistanceModel(model); }
+    }
+
+    pub fn alEnable(capability: i32) -> () {
+        unsafe { ffi::alEnable(capability); }
+    }
+
+    pub fn alDisable(capability: i32) -> () {
+        unsafe { ffi::alDisable(capability); }
+    }
+
+    pub fn alIsEnabled(capability: i32) -> bool {
+        unsafe { ffi::alIsEnabled(capability) == ffi::ALC_TRUE }
+    }
+
+    pub fn alIsExtensionPresent(extension: &str) -> bool {
+        extension.with_c_str(|c_str| unsafe {
+            ffi::alIsExtensionPresent(c_str)
+        }) == ffi::ALC_TRUE
+    }
+
+    pub fn alGetProcAddress(funcname: &str) -> *mut c_void {
+        funcname.with_c_str(|c_str| unsafe { ffi::alGetProcAddress(c_str) })
+    }
+
+    pub fn alSource3i(source: u32, param: i32, value1: i32, value2: i32, value3: i32) -> () {
+        unsafe { ffi::alSource3i(source, param, value1, value2, value3); }
+    }
+
+    /// Load the functions of the EFX extension of a device.
+    ///
+    /// The context of the device must be current.
+    pub fn load_efx(device: *mut ffi::ALCdevice) -> Option<ffi::Efx> {
+        if !alcIsExtensionPresent(device, "ALC_EXT_EFX") {
+            return None;
+        }
+        let names = ["alGenEffects", "alDeleteEffects", "alEffecti", "alEffectf",
+                     "alGenFilters", "alDeleteFilters", "alFilteri", "alFilterf",
+                     "alGenAuxiliaryEffectSlots", "alDeleteAuxiliaryEffectSlots",
+                     "alAuxiliaryEffectSloti", "alAuxiliaryEffectSlotf",
+                     "alGetAuxiliaryEffectSlotf"];
+        let functions: Vec<*mut c_void> = names.iter().map(|name| alGetProcAddress(*name)).collect();
+        if functions.iter().any(|f| f.is_null()) {
+            return None;
+        }
+        unsafe {
+            Some(ffi::Efx {
+                alGenEffects: mem::transmute(functions[0]),
+                alDeleteEffects: mem::transmute(functions[1]),
+                alEffecti: mem::transmute(functions[2]),
+                alEffectf: mem::transmute(functions[3]),
+                alGenFilters: mem::transmute(functions[4]),
+                alDeleteFilters: mem::transmute(functions[5]),
+                alFilteri: mem::transmute(functions[6]),
+                alFilterf: mem::transmute(functions[7]),
+                alGenAuxiliaryEffectSlots: mem::transmute(functions[8]),
+                alDeleteAuxiliaryEffectSlots: mem::transmute(functions[9]),
+                alAuxiliaryEffectSloti: mem::transmute(functions[10]),
+                alAuxiliaryEffectSlotf: mem::transmute(functions[11]),
+                alGetAuxiliaryEffectSlotf: mem::transmute(functions[12])
+            })
+        }
+    }
+
+    pub fn openal_has_error() -> Option<Error> {
          match unsafe { ffi::alGetError() } {
             ffi::AL_NO_ERROR          => None,
-            ffi::AL_INVALID_NAME      => Some("OpenAL error : Invalid name paramater passed to AL call.".to_string()),
-            ffi::AL_INVALID_ENUM      => Some("OpenAL error : Invalid enum parameter passed to AL call.".to_string()),
-            ffi::AL_INVALID_VALUE     => Some("OpenAL error : Invalid value parameter passed to AL call.".to_string()),
-            ffi::AL_INVALID_OPERATION => Some("OpenAL error : Illegal AL call.".to_string()),
-            ffi::AL_OUT_OF_MEMORY     => Some("OpenAL error : Not enough memory.".to_string()),
-            _                         => Some("OpenAL internal error : Unknow error.".to_string())
+            ffi::AL_INVALID_NAME      => Some(Error::OpenAlError(OpenAlError::InvalidName)),
+            ffi::AL_INVALID_ENUM      => Some(Error::OpenAlError(OpenAlError::InvalidEnum)),
+            ffi::AL_INVALID_VALUE     => Some(Error::OpenAlError(OpenAlError::InvalidValue)),
+            ffi::AL_INVALID_OPERATION => Some(Error::OpenAlError(OpenAlError::InvalidOperation)),
+            ffi::AL_OUT_OF_MEMORY     => Some(Error::OpenAlError(OpenAlError::OutOfMemory)),
+            err                       => Some(Error::OpenAlError(OpenAlError::UnknownError(err)))
         }
     }
 
+    pub fn alcIsExtensionPresent(device: *mut ffi::ALCdevice, extension: &str) -> bool {
+        extension.with_c_str(|c_str| unsafe {
+            ffi::alcIsExtensionPresent(device, c_str)
+        }) == ffi::ALC_TRUE
+    }
+
+    pub fn alcGetString(device: *mut ffi::ALCdevice, param: i32) -> Option<String> {
+        let c_str = unsafe { ffi::alcGetString(device, param) };
+        if c_str.is_null() {
+            None
+        } else {
+            unsafe { CString::new(c_str, false).as_str().map(|s| s.to_string()) }
+        }
+    }
+
+    /// Read a list of strings separated by a null character and terminated
+    /// by two null characters.
+    pub fn alcGetStringList(device: *mut ffi::ALCdevice, param: i32) -> Vec<String> {
+        let mut c_str = unsafe { ffi::alcGetString(device, param) };
+        let mut list = Vec::new();
+        if c_str.is_null() {
+            return list;
+        }
+        loop {
+            let string = unsafe { CString::new(c_str, false) };
+            let len = string.len();
+            if len == 0 {
+                break;
+            }
+            match string.as_str() {
+                Some(s) => list.push(s.to_string()),
+                None    => {}
+            }
+            c_str = unsafe { c_str.offset(len as int + 1) };
+        }
+        list
+    }
+
+    pub fn alcGetProcAddress(device: *mut ffi::ALCdevice, funcname: &str) -> *mut c_void {
+        funcname.with_c_str(|c_str| unsafe {
+            ffi::alcGetProcAddress(device, c_str)
+        })
+    }
+
     pub fn get_channels_format(channels : i32) -> Option<i32> {
         match channels {
             1 => Some(ffi::AL_FORMAT_MONO16),
             2 => Some(ffi::AL_FORMAT_STEREO16),
             4 => Some(ffi::AL_FORMAT_QUAD16),
-            5 => Some(ffi::AL_FORMAT_51CHN16),
-            6 => Some(ffi::AL_FORMAT_61CHN16),
-            7 => Some(ffi::AL_FORMAT_71CHN16),
+            // 5.1, 6.1 and 7.1 count the low-frequency channel too
+            6 => Some(ffi::AL_FORMAT_51CHN16),
+            7 => Some(ffi::AL_FORMAT_61CHN16),
+            8 => Some(ffi::AL_FORMAT_71CHN16),
             _ => return None
         }
     }
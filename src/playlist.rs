@@ -0,0 +1,687 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2014 Jeremy Letang (letang.jeremy@gmail.com)
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+//! Play a list of files without gap between the tracks.
+
+use std::cmp;
+use std::time::Duration;
+use std::sync::{Arc, Mutex};
+use std::collections::RingBuf;
+use std::rand::{task_rng, Rng};
+
+use error::Error;
+use music;
+use music::Music;
+use sndfile::SndFile;
+use sndfile::OpenMode::Read;
+use sample_source::{SampleSource, FileSource};
+use states::State;
+use audio_controller::AudioController;
+
+/// The repeat modes of a Playlist.
+#[deriving(Clone, PartialEq, Show, Copy)]
+pub enum Repeat {
+    /// Stop after the last track
+    Off,
+    /// Repeat the current track
+    One,
+    /// Go back to the first track after the last one
+    All
+}
+
+/// The events sent by a Playlist.
+#[deriving(Clone, PartialEq, Show, Copy)]
+pub enum PlaylistEvent {
+    /// The track with the given index started to play.
+    TrackChanged(uint),
+    /// The track with the given index was skipped, the file cannot be opened
+    /// or its channels or samplerate differ from the first track.
+    TrackSkipped(uint),
+    /// The last track has been queued, the playlist stops at its end.
+    Finished
+}
+
+/**
+ * Play a list of files without gap between the tracks.
+ *
+ * The tracks are streamed through a single Music: the next file is opened
+ * while the current one is playing, and its first samples are queued right
+ * after the last samples of the current one. The track changes are notified
+ * when the streaming task refills its buffers, about 100 milliseconds after
+ * the new track is heard.
+ * All the tracks must have the channels and the samplerate of the first
+ * track, the other ones are skipped.
+ *
+ * # Example
+ * ```Rust
+ * extern crate ears;
+ * use ears::{Playlist, PlaylistEvent};
+ *
+ * fn main() -> () {
+ *     let mut playlist = Playlist::new(vec!("intro.ogg".to_string(),
+ *                                           "level1.ogg".to_string()))
+ *         .unwrap();
+ *     let events = playlist.events();
+ *     playlist.play();
+ *
+ *     for event in events.iter() {
+ *         match event {
+ *             PlaylistEvent::TrackChanged(track) => println!("Now playing {}", track),
+ *             PlaylistEvent::Finished            => break,
+ *             _                                  => {}
+ *         }
+ *     }
+ * }
+ * ```
+ */
+pub struct Playlist {
+    /// The Music streaming the tracks
+    music: Music,
+    /// The state shared with the source of the Music
+    state: Arc<Mutex<PlaylistState>>
+}
+
+/// The tracks and the streaming position, shared with the PlaylistSource.
+struct PlaylistState {
+    /// The paths of the tracks
+    tracks: Vec<String>,
+    /// The indexes of the tracks in playing order
+    order: Vec<uint>,
+    shuffle: bool,
+    repeat: Repeat,
+    /// The format of the first track
+    channels: i32,
+    samplerate: i32,
+    /// The track being decoded
+    decoding: uint,
+    current: Option<FileSource>,
+    /// The next track, opened in advance
+    next: Option<(uint, FileSource)>,
+    /// Frames produced since the start of the stream
+    position: i64,
+    /// The start frame, the index and the length of the queued tracks
+    boundaries: RingBuf<(i64, uint, Option<i64>)>,
+    /// The track heard, its start frame and its length
+    playing: uint,
+    playing_start: i64,
+    playing_frames: Option<i64>,
+    /// The position of the buffers played by the Music
+    queue_offset: Option<Arc<Mutex<i64>>>,
+    events: Option<Sender<PlaylistEvent>>,
+    end_of_stream: bool
+}
+
+impl PlaylistState {
+    fn send(&self, event: PlaylistEvent) -> () {
+        match self.events {
+            Some(ref sender) => { let _ = sender.send_opt(event); },
+            None             => {}
+        }
+    }
+
+    /// The position of a track in the playing order.
+    fn position_of(&self, track: uint) -> uint {
+        self.order.iter().position(|t| *t == track).unwrap_or(0)
+    }
+
+    /// The track following the given one in playing order.
+    fn next_track(&self, track: uint, repeat: Repeat) -> Option<uint> {
+        let position = self.position_of(track);
+        match repeat {
+            Repeat::One                                 => Some(track),
+            _ if position + 1 < self.order.len()        => Some(self.order[position + 1]),
+            Repeat::All                                 => Some(self.order[0]),
+            Repeat::Off                                 => None
+        }
+    }
+
+    /// Open a track, None if it is not playable.
+    fn open(&self, track: uint) -> Option<FileSource> {
+        match SndFile::new(self.tracks[track].as_slice(), Read) {
+            Ok(file) => {
+                let infos = file.get_sndinfo();
+                if infos.channels == self.channels && infos.samplerate == self.samplerate {
+                    Some(FileSource::new(file))
+                } else {
+                    file.close();
+                    self.send(PlaylistEvent::TrackSkipped(track));
+                    None
+                }
+            },
+            Err(_)   => {
+                self.send(PlaylistEvent::TrackSkipped(track));
+                None
+            }
+        }
+    }
+
+    /// Open the first playable track from the given one in playing order.
+    fn open_from(&self, track: uint) -> Option<(uint, FileSource)> {
+        let mut track = Some(track);
+        for _ in range(0, self.order.len()) {
+            match track {
+                Some(t) => match self.open(t) {
+                    Some(source) => return Some((t, source)),
+                    None         => track = self.next_track(t, Repeat::Off)
+                },
+                None    => break
+            }
+        }
+        None
+    }
+
+    /// Open the track following the decoded one in advance.
+    fn prefetch(&mut self) -> () {
+        if self.next.is_none() {
+            self.next = match self.next_track(self.decoding, self.repeat) {
+                Some(track) => self.open_from(track),
+                None        => None
+            };
+        }
+    }
+
+    /// Restart the stream on a track, at the given frame of the track.
+    ///
+    /// Return true if the track could seek to the frame.
+    fn start(&mut self, track: uint, frame: i64) -> bool {
+        self.next = None;
+        self.boundaries.clear();
+        self.end_of_stream = false;
+        match self.open_from(track) {
+            Some((track, mut source)) => {
+                let seeked = source.seek(frame);
+                self.position = if seeked { frame } else { 0 };
+                self.decoding = track;
+                self.playing = track;
+                self.playing_start = 0;
+                self.playing_frames = source.get_frames();
+                self.current = Some(source);
+                self.send(PlaylistEvent::TrackChanged(track));
+                self.prefetch();
+                seeked
+            },
+            None                      => {
+                self.current = None;
+                self.end_of_stream = true;
+                self.send(PlaylistEvent::Finished);
+                false
+            }
+        }
+    }
+
+    /// Update the track heard from the position of the Music.
+    fn update_playing(&mut self) -> () {
+        let offset = match self.queue_offset {
+            Some(ref offset) => *offset.lock(),
+            None             => return
+        };
+        loop {
+            match self.boundaries.front() {
+                Some(&(start, _, _)) if start <= offset => {},
+                _                                      => break
+            }
+            let (start, track, frames) = self.boundaries.pop_front().unwrap();
+            self.playing = track;
+            self.playing_start = start;
+            self.playing_frames = frames;
+            self.send(PlaylistEvent::TrackChanged(track));
+        }
+    }
+}
+
+/// The SampleSource chaining the tracks of a Playlist.
+struct PlaylistSource {
+    state: Arc<Mutex<PlaylistState>>
+}
+
+impl SampleSource for PlaylistSource {
+    fn read(&mut self, buffer: &mut [i16]) -> uint {
+        let mut state = self.state.lock();
+        let channels = state.channels as uint;
+        state.update_playing();
+
+        let mut read = 0;
+        while read < buffer.len() && !state.end_of_stream {
+            let nb = match state.current {
+                Some(ref mut current) => current.read(buffer.slice_from_mut(read)),
+                None                  => 0
+            };
+            read += nb;
+            state.position += (nb / channels) as i64;
+
+            if nb == 0 {
+                // The track is over, continue with the prefetched one
+                state.prefetch();
+                match state.next.take() {
+                    Some((track, source)) => {
+                        let start = state.position;
+                        state.boundaries.push_back((start, track, source.get_frames()));
+                        state.decoding = track;
+                        state.current = Some(source);
+                        state.prefetch();
+                    },
+                    None                  => {
+                        state.current = None;
+                        state.end_of_stream = true;
+                        state.send(PlaylistEvent::Finished);
+                    }
+                }
+            }
+        }
+        read
+    }
+
+    fn get_channels(&self) -> i32 {
+        self.state.lock().channels
+    }
+
+    fn get_samplerate(&self) -> i32 {
+        self.state.lock().samplerate
+    }
+
+    fn is_end_of_stream(&self) -> bool {
+        let mut state = self.state.lock();
+        state.update_playing();
+        state.end_of_stream
+    }
+
+    /// Restart the track heard at the given frame, or the first track if the
+    /// playlist is over.
+    fn seek(&mut self, frame: i64) -> bool {
+        let mut state = self.state.lock();
+        let track = if state.end_of_stream { state.order[0] } else { state.playing };
+        state.start(track, frame)
+    }
+}
+
+impl Playlist {
+    /**
+     * Create a new Playlist.
+     *
+     * The first track is opened to get the format of the playlist.
+     *
+     * # Argument
+     * * `tracks` - The paths of the files to play
+     *
+     * # Return
+     * A Result containing Ok(Playlist) on success, Err(Error::EmptyPlaylist)
+     * if there is no tracks, or the reason why the first track cannot be
+     * opened.
+     */
+    pub fn new(tracks: Vec<String>) -> Result<Playlist, Error> {
+        if tracks.is_empty() {
+            return Err(Error::EmptyPlaylist);
+        }
+        let first = try!(SndFile::new(tracks[0].as_slice(), Read));
+        let infos = first.get_sndinfo();
+        first.close();
+
+        let state = Arc::new(Mutex::new(PlaylistState {
+            order: range(0, tracks.len()).collect(),
+            tracks: tracks,
+            shuffle: false,
+            repeat: Repeat::Off,
+            channels: infos.channels,
+            samplerate: infos.samplerate,
+            decoding: 0,
+            current: None,
+            next: None,
+            position: 0,
+            boundaries: RingBuf::new(),
+            playing: 0,
+            playing_start: 0,
+            playing_frames: Some(infos.frames),
+            queue_offset: None,
+            events: None,
+            end_of_stream: false
+        }));
+        let mut music = try!(Music::from_source(box PlaylistSource { state: state.clone() }));
+        // Short buffers, so the track changes are notified on time
+        music.set_buffer_duration(Duration::milliseconds(100));
+        music.set_buffer_count(4);
+        state.lock().queue_offset = Some(music::queue_offset(&music));
+
+        Ok(Playlist {
+            music: music,
+            state: state
+        })
+    }
+
+    /**
+     * Add a track at the end of the playlist.
+     *
+     * # Argument
+     * * `path` - The path of the file to play
+     */
+    pub fn add(&mut self, path: &str) -> () {
+        let mut state = self.state.lock();
+        let track = state.tracks.len();
+        state.tracks.push(path.to_string());
+        state.order.push(track);
+        // The prefetched track may not follow the decoded one anymore
+        state.next = None;
+    }
+
+    /// Get the number of tracks of the playlist.
+    pub fn len(&self) -> uint {
+        self.state.lock().tracks.len()
+    }
+
+    /**
+     * Get the events of the playlist.
+     *
+     * A new Receiver replaces the previous one.
+     *
+     * # Return
+     * The Receiver of the events sent while the playlist is playing.
+     */
+    pub fn events(&mut self) -> Receiver<PlaylistEvent> {
+        let (sender, receiver) = channel();
+        self.state.lock().events = Some(sender);
+        receiver
+    }
+
+    /// Play or resume the playlist.
+    pub fn play(&mut self) -> () {
+        self.music.play()
+    }
+
+    /// Pause the playlist.
+    pub fn pause(&mut self) -> () {
+        self.music.pause()
+    }
+
+    /// Stop the playlist, the current track restarts from its begining.
+    pub fn stop(&mut self) -> () {
+        // The Music restarts the current track when it is played again
+        self.music.stop();
+    }
+
+    /// Check if the playlist is playing.
+    pub fn is_playing(&self) -> bool {
+        self.music.is_playing()
+    }
+
+    /// Get the state of the playlist.
+    pub fn get_state(&self) -> State {
+        self.music.get_state()
+    }
+
+    /// Set the volume of the playlist, see AudioController::set_volume.
+    pub fn set_volume(&mut self, volume: f32) -> () {
+        self.music.set_volume(volume)
+    }
+
+    /// Get the volume of the playlist.
+    pub fn get_volume(&self) -> f32 {
+        self.music.get_volume()
+    }
+
+    /**
+     * Play a track.
+     *
+     * The track starts immediately if the playlist is playing, otherwise it
+     * is played the next time the playlist is played.
+     *
+     * # Argument
+     * * `track` - The index of the track, ignored if it doesn't exist
+     */
+    pub fn select(&mut self, track: uint) -> () {
+        if track >= self.len() {
+            return;
+        }
+        let playing = self.music.is_playing();
+        self.music.stop();
+        {
+            let mut state = self.state.lock();
+            state.playing = track;
+            state.end_of_stream = false;
+        }
+        // Playing the stopped Music restarts the stream on the track
+        if playing {
+            self.music.play();
+        }
+    }
+
+    /**
+     * Play the next track in playing order.
+     *
+     * # Return
+     * false if the current track is the last one and the playlist doesn't
+     * repeat, true otherwise.
+     */
+    pub fn next(&mut self) -> bool {
+        let next = {
+            let state = self.state.lock();
+            let repeat = if state.repeat == Repeat::One { Repeat::All } else { state.repeat };
+            state.next_track(state.playing, repeat)
+        };
+        match next {
+            Some(track) => { self.select(track); true },
+            None        => false
+        }
+    }
+
+    /**
+     * Play the previous track in playing order.
+     *
+     * The first track restarts if the playlist doesn't repeat.
+     */
+    pub fn previous(&mut self) -> () {
+        let previous = {
+            let state = self.state.lock();
+            match state.position_of(state.playing) {
+                0 if state.repeat == Repeat::All => state.order[state.order.len() - 1],
+                0                                => state.order[0],
+                position                         => state.order[position - 1]
+            }
+        };
+        self.select(previous)
+    }
+
+    /**
+     * Shuffle the playing order or restore the order of the tracks.
+     *
+     * When shuffled, the current track becomes the first one of the new
+     * order.
+     *
+     * # Argument
+     * * `shuffle` - true to play the tracks in random order
+     */
+    pub fn set_shuffle(&mut self, shuffle: bool) -> () {
+        let mut state = self.state.lock();
+        let mut order: Vec<uint> = range(0, state.tracks.len()).collect();
+        if shuffle {
+            let playing = state.playing;
+            order.retain(|t| *t != playing);
+            task_rng().shuffle(order.as_mut_slice());
+            order.insert(0, playing);
+        }
+        state.order = order;
+        state.shuffle = shuffle;
+        // The prefetched track may not follow the decoded one anymore
+        state.next = None;
+    }
+
+    /// Check if the playing order is shuffled.
+    pub fn is_shuffle(&self) -> bool {
+        self.state.lock().shuffle
+    }
+
+    /**
+     * Set the repeat mode of the playlist.
+     *
+     * # Argument
+     * * `repeat` - The new repeat mode, Repeat::Off by default
+     */
+    pub fn set_repeat(&mut self, repeat: Repeat) -> () {
+        let mut state = self.state.lock();
+        state.repeat = repeat;
+        state.next = None;
+    }
+
+    /// Get the repeat mode of the playlist.
+    pub fn get_repeat(&self) -> Repeat {
+        self.state.lock().repeat
+    }
+
+    /// Get the index of the track currently played.
+    pub fn get_current_track(&self) -> uint {
+        self.state.lock().playing
+    }
+
+    /**
+     * Get the playback position in the current track.
+     *
+     * # Return
+     * The time elapsed since the begining of the current track.
+     */
+    pub fn get_track_offset(&self) -> Duration {
+        let offset = self.music.get_offset();
+        let state = self.state.lock();
+        let start = Duration::milliseconds(state.playing_start * 1000 /
+                                           state.samplerate as i64);
+        cmp::max(offset - start, Duration::zero())
+    }
+
+    /**
+     * Get the duration of the current track.
+     *
+     * # Return
+     * The duration of the current track.
+     */
+    pub fn get_track_duration(&self) -> Duration {
+        let state = self.state.lock();
+        match state.playing_frames {
+            Some(frames) => Duration::milliseconds(frames * 1000 / state.samplerate as i64),
+            None         => Duration::zero()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(non_snake_case)]
+
+    use std::time::Duration;
+
+    use playlist::{Playlist, PlaylistEvent, Repeat};
+    use states::State::Playing;
+    use error::Error;
+
+    fn tracks(nb: uint) -> Vec<String> {
+        Vec::from_elem(nb, "res/shot.wav".to_string())
+    }
+
+    #[test]
+    fn playlist_create_OK() -> () {
+        let playlist = Playlist::new(tracks(2)).ok().expect("Cannot create Playlist");
+
+        assert_eq!(playlist.len(), 2);
+        assert_eq!(playlist.get_current_track(), 0);
+        assert_eq!(playlist.get_track_duration(), Duration::milliseconds(511));
+    }
+
+    #[test]
+    fn playlist_create_FAIL() -> () {
+        assert_eq!(Playlist::new(Vec::new()).err(), Some(Error::EmptyPlaylist));
+        assert!(Playlist::new(vec!("toto.wav".to_string())).is_err());
+    }
+
+    #[test]
+    fn playlist_play_OK() -> () {
+        let mut playlist = Playlist::new(tracks(2)).ok().expect("Cannot create Playlist");
+        let events = playlist.events();
+
+        playlist.play();
+        assert_eq!(playlist.get_state() as i32, Playing as i32);
+        assert_eq!(events.recv(), PlaylistEvent::TrackChanged(0));
+        playlist.stop();
+    }
+
+    #[test]
+    fn playlist_gapless_OK() -> () {
+        let mut playlist = Playlist::new(tracks(3)).ok().expect("Cannot create Playlist");
+        let events = playlist.events();
+
+        playlist.play();
+        assert_eq!(events.recv(), PlaylistEvent::TrackChanged(0));
+        assert_eq!(events.recv(), PlaylistEvent::TrackChanged(1));
+        // The second track is queued after the first one, without underrun
+        assert_eq!(playlist.get_state() as i32, Playing as i32);
+        assert_eq!(playlist.get_current_track(), 1);
+        playlist.stop();
+    }
+
+    #[test]
+    fn playlist_next_while_playing_OK() -> () {
+        let mut playlist = Playlist::new(tracks(2)).ok().expect("Cannot create Playlist");
+        let events = playlist.events();
+
+        playlist.play();
+        assert_eq!(events.recv(), PlaylistEvent::TrackChanged(0));
+        assert!(playlist.next());
+        assert_eq!(events.recv(), PlaylistEvent::TrackChanged(1));
+        // The track change is sent once, then the last track ends
+        assert_eq!(events.recv(), PlaylistEvent::Finished);
+        playlist.stop();
+    }
+
+    #[test]
+    fn playlist_skip_broken_track_OK() -> () {
+        let mut playlist = Playlist::new(tracks(1)).ok().expect("Cannot create Playlist");
+        playlist.add("toto.wav");
+        playlist.add("res/shot.wav");
+        let events = playlist.events();
+
+        playlist.play();
+        assert_eq!(events.recv(), PlaylistEvent::TrackChanged(0));
+        assert_eq!(events.recv(), PlaylistEvent::TrackSkipped(1));
+        playlist.stop();
+    }
+
+    #[test]
+    fn playlist_next_previous_OK() -> () {
+        let mut playlist = Playlist::new(tracks(3)).ok().expect("Cannot create Playlist");
+
+        assert!(playlist.next());
+        assert_eq!(playlist.get_current_track(), 1);
+        assert!(playlist.next());
+        assert!(!playlist.next());
+        playlist.set_repeat(Repeat::All);
+        assert!(playlist.next());
+        assert_eq!(playlist.get_current_track(), 0);
+        playlist.previous();
+        assert_eq!(playlist.get_current_track(), 2);
+    }
+
+    #[test]
+    fn playlist_shuffle_OK() -> () {
+        let mut playlist = Playlist::new(tracks(5)).ok().expect("Cannot create Playlist");
+
+        playlist.select(3);
+        playlist.set_shuffle(true);
+        assert!(playlist.is_shuffle());
+        assert_eq!(playlist.get_current_track(), 3);
+        playlist.set_shuffle(false);
+        assert!(!playlist.is_shuffle());
+    }
+}
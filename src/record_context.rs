@@ -19,22 +19,81 @@
 // IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
 // CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
 
-#![allow(raw_pointer_deriving)]
+//! The context and the capture format needed by a Recorder.
 
-use openal::ffi;
+use std::sync::Arc;
+use std::default::Default;
 
-/// The context needed to initialize a new Recorder
+use openal::{ffi, al};
+use context::AudioContext;
+use internal::CaptureDevice;
+
+/// The format of the samples captured by a Recorder.
 #[deriving(Clone, PartialEq, Show, Copy)]
+pub struct CaptureFormat {
+    /// The samplerate of the captured samples, in Hz.
+    pub samplerate: i32,
+    /// The number of channels, 1 for mono, 2 for stereo.
+    pub channels: i32,
+    /// The size of the capture ring buffer, in frames.
+    pub buffer_size: i32
+}
+
+impl Default for CaptureFormat {
+    /// 44100 Hz mono with a one second buffer.
+    fn default() -> CaptureFormat {
+        CaptureFormat {
+            samplerate: 44100,
+            channels: 1,
+            buffer_size: 44100
+        }
+    }
+}
+
+/// The context needed to initialize a new Recorder
+#[deriving(Clone)]
 pub struct RecordContext {
-    capt_device: *mut ffi::ALCdevice
+    ctxt: AudioContext,
+    capt_device: Arc<CaptureDevice>
+}
+
+impl RecordContext {
+    /// Get the format of the captured samples.
+    pub fn get_format(&self) -> CaptureFormat {
+        self.capt_device.format
+    }
+
+    /// Get the samplerate of the captured samples.
+    pub fn get_samplerate(&self) -> i32 {
+        self.capt_device.format.samplerate
+    }
+
+    /// Get the number of channels of the captured samples.
+    pub fn get_channels(&self) -> i32 {
+        self.capt_device.format.channels
+    }
+
+    /// Get the size of the capture buffer, in frames.
+    pub fn get_buffer_size(&self) -> i32 {
+        self.capt_device.format.buffer_size
+    }
+
+    /// Get the name of the capture device, or None if OpenAL doesn't provide
+    /// it.
+    pub fn get_device_name(&self) -> Option<String> {
+        al::alcGetString(self.capt_device.device, ffi::ALC_CAPTURE_DEVICE_SPECIFIER)
+    }
 }
 
-pub fn new(capt_device: *mut ffi::ALCdevice) -> RecordContext {
+#[doc(hidden)]
+pub fn new(ctxt: AudioContext, capt_device: CaptureDevice) -> RecordContext {
     RecordContext {
-        capt_device: capt_device
+        ctxt: ctxt,
+        capt_device: Arc::new(capt_device)
     }
 }
 
-pub fn get(ctxt: RecordContext) -> *mut ffi::ALCdevice {
-    ctxt.capt_device
+#[doc(hidden)]
+pub fn get(ctxt: &RecordContext) -> *mut ffi::ALCdevice {
+    ctxt.capt_device.device
 }
@@ -23,13 +23,23 @@
 
 use std::{task, mem};
 use std::vec::Vec;
+use std::time::Duration;
+use std::io::timer::sleep;
+use std::comm::TryRecvError::{Empty, Disconnected};
+use std::sync::{Arc, Mutex};
 
 use record_context::RecordContext;
 use record_context;
 use openal::ffi;
-use sndfile::{SndInfo, SndFile};
+use sndfile;
+use sndfile::{SndInfo, SndFile, FormatType};
 use sndfile::OpenMode::Write;
 use sndfile::FormatType::{FormatWav, FormatPcm16};
+use error;
+use error::Error;
+use input_level::{InputLevel, VoiceDetection, VoiceActivityDetector, VoiceEvent};
+
+const SILENCE: InputLevel = InputLevel { rms: 0., peak: 0. };
 
 /**
  * Record audio
@@ -37,6 +47,9 @@ use sndfile::FormatType::{FormatWav, FormatPcm16};
  * This class provide easy audio recording using. The Recorder allow the user
  * to record sound, then save it in a file, or create a SoundData object to play the
  * recorded sound in the same program.
+ * The captured samples can also be received as they arrive with
+ * start_streaming, or written directly in a file with start_to_file, to
+ * record long sessions without keeping them in memory.
  * A special context, RecordContext is needed to create the Recorder object.
  * The Recorder work in it's own task.
  *
@@ -56,17 +69,170 @@ use sndfile::FormatType::{FormatWav, FormatPcm16};
  *     // Do some other stuff here //
  *
  *     // Stop the recorder
- *     recorder.stop();
+ *     recorder.stop().unwrap();
  *     // Then store the recorded data in a file
- *     recorder.save_to_file("hello_file");
+ *     recorder.save_to_file("hello_file").unwrap();
  * }
  * ```
  */
 pub struct Recorder {
     ctxt: RecordContext,
-    stop_sender: Option<Sender<bool>>,
-    data_receiver: Option<Receiver<Vec<i16>>>,
-    samples: Vec<i16>
+    command_sender: Option<Sender<Command>>,
+    data_receiver: Option<Receiver<Result<Option<Vec<i16>>, Error>>>,
+    samples: Vec<i16>,
+    latency: Duration,
+    paused: bool,
+    meter: Arc<Mutex<Meter>>
+}
+
+/// The commands sent to the capture task.
+enum Command {
+    Stop,
+    Pause,
+    Resume,
+    SetPollDelay(Duration)
+}
+
+/// Where the capture task delivers the captured samples.
+enum Output {
+    /// Keep all the samples, they are returned when the record stops.
+    Memory(Vec<i16>),
+    /// Send each captured chunk.
+    Stream(Sender<Vec<i16>>),
+    /// Write each captured chunk in a file.
+    File(SndFile)
+}
+
+impl Output {
+    fn write(&mut self, mut chunk: Vec<i16>) -> Result<(), Error> {
+        match *self {
+            Output::Memory(ref mut samples) => {
+                samples.extend(chunk.into_iter());
+                Ok(())
+            },
+            Output::Stream(ref sender)      => {
+                // The receiver may have been dropped, the chunk is lost.
+                let _ = sender.send_opt(chunk);
+                Ok(())
+            },
+            Output::File(ref mut file)      => {
+                let len = chunk.len() as i64;
+                if file.write_i16(chunk.as_mut_slice(), len) == len {
+                    Ok(())
+                } else {
+                    Err(file.get_error())
+                }
+            }
+        }
+    }
+
+    /// Return the samples recorded in memory, if any.
+    fn finish(self) -> Result<Option<Vec<i16>>, Error> {
+        match self {
+            Output::Memory(samples) => Ok(Some(samples)),
+            Output::Stream(_)       => Ok(None),
+            Output::File(file)      => match file.close() {
+                sndfile::Error::NoError => Ok(None),
+                err                     => Err(error::from_sndfile(err,
+                                                   "cannot close the file".to_string()))
+            }
+        }
+    }
+}
+
+/// The level and the voice activity shared with the capture task.
+struct Meter {
+    level: InputLevel,
+    detector: Option<VoiceActivityDetector>,
+    voice_events: Option<Sender<VoiceEvent>>,
+    /// Frames delivered to the output since the start of the record
+    recorded: i64
+}
+
+impl Meter {
+    fn send(&self, event: VoiceEvent) -> () {
+        match self.voice_events {
+            Some(ref sender) => { let _ = sender.send_opt(event); },
+            None             => {}
+        }
+    }
+
+    /// Make the voice inactive, ending the current segment if any.
+    fn reset_voice(&mut self) -> () {
+        let active = match self.detector {
+            Some(ref mut detector) => {
+                let active = detector.is_active();
+                detector.reset();
+                active
+            },
+            None                   => false
+        };
+        if active {
+            let recorded = self.recorded;
+            self.send(VoiceEvent::SegmentEnded(recorded));
+        }
+    }
+}
+
+/// Move the available captured samples to the output.
+///
+/// Measure the level of the samples, send the start and the end of the voice
+/// segments, and drop the samples if the voice detection gate is closed.
+/// Stop writing after the first failure, which is kept in result.
+fn capture(ctxt: *mut ffi::ALCdevice,
+           channels: i32,
+           samplerate: i32,
+           meter: &Mutex<Meter>,
+           output: &mut Output,
+           result: &mut Result<(), Error>) -> () {
+    let mut available_samples = 0;
+    unsafe {
+        ffi::alcGetIntegerv(ctxt,
+                            ffi::ALC_CAPTURE_SAMPLES,
+                            1,
+                            &mut available_samples)
+    };
+
+    if available_samples != 0 {
+        // available_samples is a number of frames
+        let tmp_buf =
+            Vec::from_elem((available_samples * channels) as uint, 0i16);
+        unsafe {
+            ffi::alcCaptureSamples(ctxt,
+                                   mem::transmute(&tmp_buf.as_slice()[0]),
+                                   available_samples);
+        }
+
+        let duration = Duration::milliseconds(available_samples as i64 * 1000 /
+                                              samplerate as i64);
+        let level = InputLevel::from_samples(tmp_buf.as_slice());
+        let deliver = {
+            let mut meter = meter.lock();
+            meter.level = level;
+            let (was_active, active, gate) = match meter.detector {
+                Some(ref mut detector) => {
+                    let was_active = detector.is_active();
+                    (was_active, detector.process(level, duration),
+                     detector.get_settings().gate)
+                },
+                None                   => (false, false, false)
+            };
+            let recorded = meter.recorded;
+            if active && !was_active {
+                meter.send(VoiceEvent::SegmentStarted(recorded));
+            } else if was_active && !active {
+                meter.send(VoiceEvent::SegmentEnded(recorded));
+            }
+            let deliver = (active || !gate) && result.is_ok();
+            if deliver {
+                meter.recorded += available_samples as i64;
+            }
+            deliver
+        };
+        if deliver {
+            *result = output.write(tmp_buf);
+        }
+    }
 }
 
 impl Recorder {
@@ -74,99 +240,465 @@ impl Recorder {
     pub fn new(record_context: RecordContext) -> Recorder {
         Recorder {
             ctxt: record_context,
-            stop_sender: None,
+            command_sender: None,
             data_receiver: None,
-            samples: Vec::new()
-
+            samples: Vec::new(),
+            latency: Duration::milliseconds(20),
+            paused: false,
+            meter: Arc::new(Mutex::new(Meter {
+                level: SILENCE,
+                detector: None,
+                voice_events: None,
+                recorded: 0
+            }))
         }
     }
 
+    /**
+     * Start to record in memory.
+     *
+     * The recorded samples are available when the record is stopped.
+     * If the Recorder is already recording, the current record is stopped.
+     */
     pub fn start(&mut self) {
-        let (stop_sender, stop_receiver) = channel();
+        self.start_with(Output::Memory(Vec::new()));
+    }
+
+    /**
+     * Start to record, and send the captured samples as they arrive.
+     *
+     * Each message is a chunk of interleaved samples in the format of the
+     * RecordContext, the samples are not kept by the Recorder.
+     * If the Recorder is already recording, the current record is stopped.
+     *
+     * # Return
+     * The Receiver of the captured chunks, it is closed when the record is
+     * stopped.
+     *
+     * # Example
+     * ```Rust
+     * let chunks = recorder.start_streaming();
+     * for chunk in chunks.iter() {
+     *     update_level_meter(chunk.as_slice());
+     * }
+     * ```
+     */
+    pub fn start_streaming(&mut self) -> Receiver<Vec<i16>> {
+        let (sender, receiver) = channel();
+        self.start_with(Output::Stream(sender));
+        receiver
+    }
+
+    /**
+     * Start to record directly in a file.
+     *
+     * The captured samples are written in the file as they arrive and are not
+     * kept by the Recorder. The file is closed when the record is stopped.
+     * If the Recorder is already recording, the current record is stopped.
+     *
+     * # Arguments
+     * * `path` - The path of the file to write
+     * * `container` - The major format of the file
+     * * `subtype` - The encoding of the samples
+     *
+     * # Return
+     * Ok(()) if the file is opened and the record started, the reason of the
+     * failure otherwise.
+     */
+    pub fn start_to_file(&mut self,
+                         path: &str,
+                         container: FormatType,
+                         subtype: FormatType) -> Result<(), Error> {
+        let file = try!(self.create_file(path, container, subtype, 0));
+        self.start_with(Output::File(file));
+        Ok(())
+    }
+
+    fn start_with(&mut self, output: Output) {
+        let _ = self.stop();
+
+        let (command_sender, command_receiver) = channel();
         let (data_sender, data_receiver) = channel();
         let r_c = self.ctxt.clone();
+        let poll_delay = self.get_poll_delay();
+        let meter = self.meter.clone();
 
-        self.stop_sender = Some(stop_sender);
+        self.command_sender = Some(command_sender);
         self.data_receiver = Some(data_receiver);
+        self.paused = false;
+        {
+            // The hangover of the previous record doesn't carry over
+            let mut meter = self.meter.lock();
+            meter.reset_voice();
+            meter.recorded = 0;
+        }
 
         task::spawn(proc() {
-            let mut terminate = false;
-            let ctxt = record_context::get(r_c);
+            let mut output = output;
+            let mut result = Ok(());
+            let mut paused = false;
+            let mut poll_delay = poll_delay;
+            let ctxt = record_context::get(&r_c);
+            let channels = r_c.get_channels();
+            let samplerate = r_c.get_samplerate();
             unsafe { ffi::alcCaptureStart(ctxt); }
-            let mut available_samples = 0;
-            let mut samples: Vec<i16> = Vec::new();
-
-            while !terminate {
-                unsafe {
-                    ffi::alcGetIntegerv(ctxt,
-                                        ffi::ALC_CAPTURE_SAMPLES,
-                                        1,
-                                        &mut available_samples)
-                };
 
-                if available_samples != 0 {
-                    let tmp_buf =
-                        Vec::from_elem(available_samples as uint, 0i16);
-                    unsafe {
-                        ffi::alcCaptureSamples(ctxt,
-                                               mem::transmute(&tmp_buf.as_slice()[0]),
-                                               available_samples);
-                    }
-                    samples.extend(tmp_buf.into_iter());
+            loop {
+                if !paused {
+                    capture(ctxt, channels, samplerate, &*meter,
+                            &mut output, &mut result);
                 }
 
-                match stop_receiver.try_recv() {
-                    Ok(_) => {
-                        unsafe { ffi::alcCaptureStop(ctxt); }
-                        terminate = true;
+                // Wait the next command without spinning while paused.
+                let command = if paused {
+                    match command_receiver.recv_opt() {
+                        Ok(command) => Some(command),
+                        Err(_)      => Some(Command::Stop)
+                    }
+                } else {
+                    match command_receiver.try_recv() {
+                        Ok(command)       => Some(command),
+                        Err(Disconnected) => Some(Command::Stop),
+                        Err(Empty)        => None
+                    }
+                };
+
+                match command {
+                    Some(Command::Stop)              => {
+                        if !paused {
+                            unsafe { ffi::alcCaptureStop(ctxt); }
+                            capture(ctxt, channels, samplerate, &*meter,
+                                    &mut output, &mut result);
+                        }
+                        let mut meter = meter.lock();
+                        meter.level = SILENCE;
+                        meter.reset_voice();
+                        break;
+                    },
+                    Some(Command::Pause)             => {
+                        if !paused {
+                            unsafe { ffi::alcCaptureStop(ctxt); }
+                            capture(ctxt, channels, samplerate, &*meter,
+                                    &mut output, &mut result);
+                            let mut meter = meter.lock();
+                            meter.level = SILENCE;
+                            meter.reset_voice();
+                            paused = true;
+                        }
                     },
-                    _       => {}
+                    Some(Command::Resume)            => {
+                        if paused {
+                            unsafe { ffi::alcCaptureStart(ctxt); }
+                            paused = false;
+                        }
+                    },
+                    Some(Command::SetPollDelay(delay)) => poll_delay = delay,
+                    None                             => sleep(poll_delay)
                 }
             }
-            data_sender.send(samples);
+            data_sender.send(match result {
+                Ok(_)    => output.finish(),
+                Err(err) => { let _ = output.finish(); Err(err) }
+            });
         });
     }
 
-    pub fn stop(&mut self) -> bool {
-        match self.stop_sender {
-            Some(ref s_c) => {
-                s_c.send(true);
-                match self.data_receiver {
-                    Some(ref d_p) => {
-                        self.samples = d_p.recv();
-                        true
+    /**
+     * Pause the record.
+     *
+     * The capture device stays opened, the samples captured before the
+     * pause are delivered and the record can be resumed later.
+     */
+    pub fn pause(&mut self) -> () {
+        match self.command_sender {
+            Some(ref sender) => {
+                sender.send(Command::Pause);
+                self.paused = true;
+            },
+            None             => {}
+        }
+    }
+
+    /// Resume a paused record.
+    pub fn resume(&mut self) -> () {
+        match self.command_sender {
+            Some(ref sender) => {
+                sender.send(Command::Resume);
+                self.paused = false;
+            },
+            None             => {}
+        }
+    }
+
+    /**
+     * Check if the Recorder is recording.
+     *
+     * # Return
+     * true if the Recorder is started and not paused, false otherwise.
+     */
+    pub fn is_recording(&self) -> bool {
+        self.command_sender.is_some() && !self.paused
+    }
+
+    /**
+     * Check if the record is paused.
+     *
+     * # Return
+     * true if the Recorder is started and paused, false otherwise.
+     */
+    pub fn is_paused(&self) -> bool {
+        self.command_sender.is_some() && self.paused
+    }
+
+    /**
+     * Set the latency target of the capture.
+     *
+     * The capture task collects the captured samples at this interval, or
+     * more often if the capture buffer of the RecordContext would overflow.
+     * A lower latency delivers the samples sooner but wakes the task more
+     * often. The default latency is 20 milliseconds.
+     * The latency can be changed while recording.
+     *
+     * # Argument
+     * * `latency` - The new latency target
+     */
+    pub fn set_latency(&mut self, latency: Duration) -> () {
+        self.latency = latency;
+        let poll_delay = self.get_poll_delay();
+        match self.command_sender {
+            Some(ref sender) => sender.send(Command::SetPollDelay(poll_delay)),
+            None             => {}
+        }
+    }
+
+    /**
+     * Get the latency target of the capture.
+     *
+     * # Return
+     * The latency target
+     */
+    pub fn get_latency(&self) -> Duration {
+        self.latency
+    }
+
+    /**
+     * Get the level of the last captured chunk.
+     *
+     * The level can be polled while recording to display a level meter, it
+     * is zero when the Recorder is stopped or paused.
+     *
+     * # Return
+     * The rms and peak level of the last captured samples
+     */
+    pub fn get_level(&self) -> InputLevel {
+        self.meter.lock().level
+    }
+
+    /**
+     * Enable or disable the voice activity detection.
+     *
+     * When the detection is enabled the voice activity is updated with each
+     * captured chunk, and the start and the end of each segment of voice are
+     * sent to the Receiver of voice_events. If the gate of the settings is
+     * set, only the samples captured while the voice is active are recorded,
+     * the segments follow each other in the record without separation, so
+     * the frames of the events are needed to split them.
+     * The voice becomes inactive when the record is paused or stopped.
+     *
+     * # Argument
+     * * `settings` - The settings of the detection, or None to disable it
+     *
+     * # Example
+     * ```Rust
+     * recorder.set_voice_detection(Some(VoiceDetection {
+     *     threshold: 0.05,
+     *     hangover: Duration::milliseconds(500),
+     *     gate: true
+     * }));
+     * let segments = recorder.voice_events();
+     * let chunks = recorder.start_streaming();
+     * ```
+     */
+    pub fn set_voice_detection(&mut self, settings: Option<VoiceDetection>) -> () {
+        let mut meter = self.meter.lock();
+        meter.reset_voice();
+        meter.detector = settings.map(|s| VoiceActivityDetector::new(s));
+    }
+
+    /**
+     * Get the voice activity events.
+     *
+     * Each event gives the position in frames in the record of the start or
+     * the end of a segment of voice. A new Receiver replaces the previous
+     * one.
+     *
+     * # Return
+     * The Receiver of the events sent while the voice detection is enabled.
+     *
+     * # Example
+     * ```Rust
+     * for event in recorder.voice_events().iter() {
+     *     match event {
+     *         VoiceEvent::SegmentStarted(frame) => println!("Talking at {}", frame),
+     *         VoiceEvent::SegmentEnded(frame)   => println!("Silent at {}", frame)
+     *     }
+     * }
+     * ```
+     */
+    pub fn voice_events(&mut self) -> Receiver<VoiceEvent> {
+        let (sender, receiver) = channel();
+        self.meter.lock().voice_events = Some(sender);
+        receiver
+    }
+
+    /**
+     * Check if the voice is active.
+     *
+     * # Return
+     * true if the voice detection is enabled and the voice is active, false
+     * otherwise.
+     */
+    pub fn is_voice_active(&self) -> bool {
+        match self.meter.lock().detector {
+            Some(ref detector) => detector.is_active(),
+            None               => false
+        }
+    }
+
+    /// Get the delay between two polls of the capture device, the latency
+    /// target bounded by half the duration of the capture buffer.
+    fn get_poll_delay(&self) -> Duration {
+        let buffer = Duration::milliseconds(self.ctxt.get_buffer_size() as i64 * 1000 /
+                                            self.ctxt.get_samplerate() as i64 / 2);
+        let delay = if self.latency < buffer { self.latency } else { buffer };
+        if delay < Duration::milliseconds(1) {
+            Duration::milliseconds(1)
+        } else {
+            delay
+        }
+    }
+
+    /**
+     * Stop the record.
+     *
+     * When recording in memory, the recorded samples replace the previous
+     * ones.
+     *
+     * # Return
+     * Ok if the record is stopped without error, Err(Error::NotRecording) if
+     * the Recorder was not recording, or the reason why the samples cannot
+     * be written in the file.
+     */
+    pub fn stop(&mut self) -> Result<(), Error> {
+        self.paused = false;
+        match (self.command_sender.take(), self.data_receiver.take()) {
+            (Some(c_s), Some(d_p)) => {
+                c_s.send(Command::Stop);
+                match d_p.recv() {
+                    Ok(Some(samples)) => {
+                        self.samples = samples;
+                        Ok(())
                     },
-                    None          => false
+                    Ok(None)          => Ok(()),
+                    Err(err)          => Err(err)
                 }
             },
-            None      => false
+            _                      => Err(Error::NotRecording)
         }
     }
 
-    pub fn save_to_file(&mut self, filename: &str) -> bool {
+    /**
+     * Save the recorded samples in a WAV file.
+     *
+     * The samples are written as 16 bits PCM, the extension ".wav" is
+     * appended to the filename.
+     *
+     * # Argument
+     * * `filename` - The name of the file without extension
+     *
+     * # Return
+     * Ok if the file is written, Err(Error::NoRecordedSamples) if there is no
+     * recorded samples, or the reason why the file cannot be written.
+     */
+    pub fn save_to_file(&mut self, filename: &str) -> Result<(), Error> {
         if self.samples.len() == 0 {
-            false
+            Err(Error::NoRecordedSamples)
         } else {
-            let infos = box SndInfo {
-                frames : self.samples.len() as i64,
-                samplerate : 44100,
-                channels : 1,
-                format : (FormatPcm16 | FormatWav) as i32,
-                sections : 0,
-                seekable : 0
-            };
-
             let mut file_ext = String::from_str(filename);
             file_ext.push_str(".wav");
-            match SndFile::new_with_info(file_ext.as_slice(), Write, infos) {
-                Ok(mut f) => {
-                    let len = self.samples.len() as i64;
-                    f.write_i16(self.samples.as_mut_slice(), len);
-                    f.close();
-                    true
-                },
-                Err(e) => { println!("{}", e); false }
-            }
+            self.save_to_file_with(file_ext.as_slice(), FormatWav, FormatPcm16)
+        }
+    }
+
+    /**
+     * Save the recorded samples in a file with an explicit format.
+     *
+     * The file is written at the exact given path, with the samplerate and
+     * the channels of the RecordContext.
+     *
+     * # Arguments
+     * * `path` - The path of the file to write
+     * * `container` - The major format of the file, e.g. FormatWav,
+     * FormatFlac, FormatOgg or FormatAiff
+     * * `subtype` - The encoding of the samples, e.g. FormatPcm16,
+     * FormatFloat or FormatVorbis
+     *
+     * # Return
+     * Ok(()) if the file is written, Err(Error::UnsupportedEncoding) if the
+     * container doesn't support the subtype, or the error raised by
+     * libsndfile otherwise.
+     *
+     * # Example
+     * ```Rust
+     * use ears::FormatType::{FormatOgg, FormatVorbis};
+     *
+     * recorder.save_to_file_with("voice.ogg", FormatOgg, FormatVorbis).unwrap();
+     * ```
+     */
+    pub fn save_to_file_with(&mut self,
+                             path: &str,
+                             container: FormatType,
+                             subtype: FormatType) -> Result<(), Error> {
+        let len = self.samples.len() as i64;
+        let frames = len / self.ctxt.get_channels() as i64;
+        let mut file = try!(self.create_file(path, container, subtype, frames));
+        if file.write_i16(self.samples.as_mut_slice(), len) != len {
+            let err = file.get_error();
+            file.close();
+            return Err(err);
+        }
+        match file.close() {
+            sndfile::Error::NoError => Ok(()),
+            err                     => Err(error::from_sndfile(err,
+                                               "cannot close the file".to_string()))
         }
     }
+
+    /// Create a file in the format of the RecordContext.
+    fn create_file(&self,
+                   path: &str,
+                   container: FormatType,
+                   subtype: FormatType,
+                   frames: i64) -> Result<SndFile, Error> {
+        let mut infos = box SndInfo {
+            frames : frames,
+            samplerate : self.ctxt.get_samplerate(),
+            channels : self.ctxt.get_channels(),
+            format : (container | subtype) as i32,
+            sections : 0,
+            seekable : 0
+        };
+        if !SndFile::check_format(&mut *infos) {
+            return Err(Error::UnsupportedEncoding(
+                format!("{} cannot be encoded as {}", container, subtype)));
+        }
+        SndFile::new_with_info(path, Write, infos)
+    }
+}
+
+impl Drop for Recorder {
+    /// Stop the record.
+    fn drop(&mut self) -> () {
+        let _ = self.stop();
+    }
 }
@@ -0,0 +1,201 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2014 Jeremy Letang (letang.jeremy@gmail.com)
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+//! Producers of samples streamed by a Music.
+
+use sndfile::{SndFile, SndInfo};
+use sndfile::SeekMode::SeekSet;
+
+/**
+ * A producer of samples streamed by a Music.
+ *
+ * Implement this trait to stream audio produced by the program through a
+ * Music, e.g. a synthesizer, a network decoder or generated audio.
+ * The samples are requested from the streaming task of the Music each time
+ * one of its buffers has been played.
+ *
+ * # Example
+ * ```Rust
+ * extern crate ears;
+ * use ears::{Music, SampleSource, AudioController};
+ *
+ * // A never ending square wave.
+ * struct Square {
+ *     position: uint
+ * }
+ *
+ * impl SampleSource for Square {
+ *     fn read(&mut self, buffer: &mut [i16]) -> uint {
+ *         for sample in buffer.iter_mut() {
+ *             *sample = if (self.position / 50) % 2 == 0 { 8000 } else { -8000 };
+ *             self.position += 1;
+ *         }
+ *         buffer.len()
+ *     }
+ *
+ *     fn get_channels(&self) -> i32 { 1 }
+ *     fn get_samplerate(&self) -> i32 { 44100 }
+ *     fn is_end_of_stream(&self) -> bool { false }
+ * }
+ *
+ * fn main() -> () {
+ *     let mut msc = Music::from_source(box Square { position: 0 }).unwrap();
+ *     msc.play();
+ * }
+ * ```
+ */
+pub trait SampleSource: Send {
+    /**
+     * Fill a buffer with interleaved samples.
+     *
+     * # Argument
+     * * `buffer` - The buffer to fill, its length is always a multiple of
+     * the number of channels.
+     *
+     * # Return
+     * The number of samples written in the buffer, 0 if no samples are
+     * available. It must be a multiple of the number of channels, so the
+     * buffer always ends with a whole frame.
+     */
+    fn read(&mut self, buffer: &mut [i16]) -> uint;
+
+    /**
+     * Get the number of channels of the samples.
+     *
+     * # Return
+     * The number of channels, must not change while streaming.
+     */
+    fn get_channels(&self) -> i32;
+
+    /**
+     * Get the samplerate of the samples.
+     *
+     * # Return
+     * The number of frames per second, must not change while streaming.
+     */
+    fn get_samplerate(&self) -> i32;
+
+    /**
+     * Check if the source has produced all its samples.
+     *
+     * # Return
+     * True if no more samples will be produced, false otherwise.
+     */
+    fn is_end_of_stream(&self) -> bool;
+
+    /**
+     * Move the source to a given frame.
+     *
+     * The default implementation does not support seeking.
+     *
+     * # Argument
+     * * `frame` - The frame to move to, from the begining of the stream.
+     *
+     * # Return
+     * True if the source has moved, false if seeking is not supported.
+     */
+    fn seek(&mut self, _frame: i64) -> bool {
+        false
+    }
+
+    /**
+     * Get the total number of frames of the source.
+     *
+     * The default implementation returns None.
+     *
+     * # Return
+     * Some(frames) if the length of the stream is known, None otherwise.
+     */
+    fn get_frames(&self) -> Option<i64> {
+        None
+    }
+
+    /**
+     * Get the loop points stored with the samples.
+     *
+     * The default implementation returns None.
+     *
+     * # Return
+     * Some((start, end)) in frames, the end frame excluded, if the source
+     * defines a loop, None otherwise.
+     */
+    fn get_loop_points(&self) -> Option<(i64, i64)> {
+        None
+    }
+}
+
+/// A SampleSource decoding the samples of a file with libsndfile.
+pub struct FileSource {
+    file: SndFile,
+    infos: SndInfo,
+    end_of_stream: bool
+}
+
+impl FileSource {
+    /// Stream the samples of an opened file.
+    pub fn new(file: SndFile) -> FileSource {
+        FileSource {
+            infos: file.get_sndinfo(),
+            file: file,
+            end_of_stream: false
+        }
+    }
+}
+
+impl SampleSource for FileSource {
+    fn read(&mut self, buffer: &mut [i16]) -> uint {
+        let channels = self.infos.channels as i64;
+        let frames = buffer.len() as i64 / channels;
+        match self.file.readf_i16(buffer, frames) {
+            read if read > 0 => (read * channels) as uint,
+            _                => { self.end_of_stream = true; 0 }
+        }
+    }
+
+    fn get_channels(&self) -> i32 {
+        self.infos.channels
+    }
+
+    fn get_samplerate(&self) -> i32 {
+        self.infos.samplerate
+    }
+
+    fn is_end_of_stream(&self) -> bool {
+        self.end_of_stream
+    }
+
+    fn seek(&mut self, frame: i64) -> bool {
+        if self.file.seek(frame, SeekSet) < 0 {
+            false
+        } else {
+            self.end_of_stream = false;
+            true
+        }
+    }
+
+    fn get_frames(&self) -> Option<i64> {
+        Some(self.infos.frames)
+    }
+
+    fn get_loop_points(&self) -> Option<(i64, i64)> {
+        self.file.get_loop_points()
+    }
+}
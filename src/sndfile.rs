@@ -32,8 +32,13 @@
 
 #![allow(dead_code)]
 
-use std::ptr;
+use std::{ptr, mem, raw};
 use std::c_str::CString;
+use std::io::SeekStyle;
+use libc::c_void;
+
+use error;
+use error::Error as EarsError;
 
 #[doc(hidden)]
 #[cfg(any(target_os="macos", target_os="linux", target_os="win32"))]
@@ -229,20 +234,25 @@ impl BitOr<FormatType, int> for FormatType {
     }
 }
 
-/// SndFile object, used to load/store sound from a file path or an fd.
+/// A seekable stream of encoded audio data, used to load a sound through the
+/// libsndfile virtual IO.
+pub trait VirtualIo: Reader + Seek + Send {}
+
+impl<T: Reader + Seek + Send> VirtualIo for T {}
+
+/// SndFile object, used to load/store sound from a file path, an fd or a
+/// reader.
+///
+/// The file is closed when the SndFile is dropped, call close to get the
+/// result of the closing.
 pub struct SndFile {
     handle : *mut ffi::SNDFILE,
-    info : Box<SndInfo>
+    info : Box<SndInfo>,
+    /// The stream read through the virtual IO, owned by the SndFile
+    io : *mut Box<VirtualIo + Send>
 }
 
-impl Clone for SndFile {
-    fn clone(&self) -> SndFile {
-        SndFile {
-            handle : self.handle,
-            info : self.info.clone()
-        }
-    }
-}
+unsafe impl Send for SndFile {}
 
 impl SndFile {
     /**
@@ -252,10 +262,10 @@ impl SndFile {
      * * path - The path to load the music
      * * mode - The mode to open the music
      *
-     * Return Ok() containing the SndFile on success, a string representation of
-     * the error otherwise.
+     * Return Ok() containing the SndFile on success, the reason of the
+     * failure otherwise.
      */
-    pub fn new(path : &str, mode : OpenMode) -> Result<SndFile, String> {
+    pub fn new(path : &str, mode : OpenMode) -> Result<SndFile, EarsError> {
         let mut info = box SndInfo {
             frames : 0,
             samplerate : 0,
@@ -268,13 +278,12 @@ impl SndFile {
             unsafe {ffi::sf_open(c_path as *mut i8, mode as i32, &mut *info) }
         });
         if tmp_sndfile.is_null() {
-            Err(unsafe {
-                CString::new(ffi::sf_strerror(ptr::null_mut()) as *const i8, false).as_str().unwrap().to_string()
-            })
+            Err(SndFile::open_error())
         } else {
             Ok(SndFile {
                 handle :    tmp_sndfile,
-                info :      info
+                info :      info,
+                io :        ptr::null_mut()
             })
         }
     }
@@ -287,21 +296,20 @@ impl SndFile {
      * * mode - The mode to open the music
      * * info - The SndInfo to pass to the file
      *
-     * Return Ok() containing the SndFile on success, a string representation of
-     * the error otherwise.
+     * Return Ok() containing the SndFile on success, the reason of the
+     * failure otherwise.
      */
-    pub fn new_with_info(path : &str, mode : OpenMode, mut info: Box<SndInfo>) -> Result<SndFile, String> {
+    pub fn new_with_info(path : &str, mode : OpenMode, mut info: Box<SndInfo>) -> Result<SndFile, EarsError> {
         let tmp_sndfile = path.with_c_str(|c_path| {
             unsafe {ffi::sf_open(c_path as *mut i8, mode as i32, &mut *info) }
         });
         if tmp_sndfile.is_null() {
-            Err(unsafe {
-                CString::new(ffi::sf_strerror(ptr::null_mut()) as *const i8, false).as_str().unwrap().to_string()
-            })
+            Err(SndFile::open_error())
         } else {
             Ok(SndFile {
                 handle :    tmp_sndfile,
-                info :      info
+                info :      info,
+                io :        ptr::null_mut()
             })
         }
     }
@@ -315,13 +323,13 @@ impl SndFile {
      * * mode - The mode to open the music
      * * close_desc - Should SndFile close the fd at exit?
      *
-     * Return Ok() containing the SndFile on success, a string representation
-     * of the error otherwise.
+     * Return Ok() containing the SndFile on success, the reason of the
+     * failure otherwise.
      */
     pub fn new_with_fd(fd : i32,
                        mode : OpenMode,
                        close_desc : bool)
-                       -> Result<SndFile, String> {
+                       -> Result<SndFile, EarsError> {
         let mut info = box SndInfo {
             frames : 0,
             samplerate : 0,
@@ -339,17 +347,78 @@ impl SndFile {
             }
         };
         if tmp_sndfile.is_null() {
-            Err(unsafe {
-                CString::new(ffi::sf_strerror(ptr::null_mut()) as *const i8, false).as_str().unwrap().to_string()
+            Err(SndFile::open_error())
+        } else {
+            Ok(SndFile {
+                handle :    tmp_sndfile,
+                info :      info,
+                io :        ptr::null_mut()
             })
+        }
+    }
+
+    /**
+     * Construct SndFile object reading the music from a seekable stream.
+     *
+     * The stream is accessed through the libsndfile virtual IO, and is
+     * owned by the SndFile until it is closed.
+     *
+     * # Argument
+     * * io - The stream containing the encoded music
+     *
+     * Return Ok() containing the SndFile on success, the reason of the
+     * failure otherwise.
+     */
+    pub fn new_with_reader(io : Box<VirtualIo + Send>) -> Result<SndFile, EarsError> {
+        let mut info = box SndInfo {
+            frames : 0,
+            samplerate : 0,
+            channels : 0,
+            format : 0,
+            sections : 0,
+            seekable : 0
+        };
+        let mut vio = ffi::SF_VIRTUAL_IO {
+            get_filelen : vio_get_filelen,
+            seek :        vio_seek,
+            read :        vio_read,
+            write :       vio_write,
+            tell :        vio_tell
+        };
+        let io: *mut Box<VirtualIo + Send> = unsafe { mem::transmute(box io) };
+        let tmp_sndfile = unsafe {
+            ffi::sf_open_virtual(&mut vio, ffi::SFM_READ, &mut *info, io as *mut c_void)
+        };
+        if tmp_sndfile.is_null() {
+            let _: Box<Box<VirtualIo + Send>> = unsafe { mem::transmute(io) };
+            Err(SndFile::open_error())
         } else {
             Ok(SndFile {
                 handle :    tmp_sndfile,
-                info :      info
+                info :      info,
+                io :        io
             })
         }
     }
 
+    /// Retrieve the error raised by the last failed call to sf_open.
+    fn open_error() -> EarsError {
+        SndFile::error_of(ptr::null_mut())
+    }
+
+    /// Retrieve the last error raised by an operation on the file.
+    pub fn get_error(&self) -> EarsError {
+        SndFile::error_of(self.handle)
+    }
+
+    fn error_of(handle: *mut ffi::SNDFILE) -> EarsError {
+        unsafe {
+            let message = CString::new(ffi::sf_strerror(handle) as *const i8, false)
+                .as_str().unwrap().to_string();
+            error::from_sndfile(ffi::sf_error(handle), message)
+        }
+    }
+
     /// Return the SndInfo struct of the current music.
     pub fn get_sndinfo(&self) -> SndInfo {
         *self.info.clone()
@@ -395,6 +464,32 @@ impl SndFile {
         }
     }
 
+    /**
+     * Retrieve the first loop of the instrument chunk of the file.
+     *
+     * The loops are stored in the SMPL chunk of WAV files and in the INST
+     * chunk of AIFF files.
+     *
+     * Return Some((start, end)) in frames if the file has a loop, the end
+     * frame is excluded, None otherwise.
+     */
+    pub fn get_loop_points(&self) -> Option<(i64, i64)> {
+        let mut instrument: ffi::SF_INSTRUMENT = unsafe { mem::zeroed() };
+        let found = unsafe {
+            ffi::sf_command(self.handle,
+                            ffi::SFC_GET_INSTRUMENT,
+                            &mut instrument as *mut ffi::SF_INSTRUMENT as *mut c_void,
+                            mem::size_of::<ffi::SF_INSTRUMENT>() as i32)
+        } as i32 == ffi::SF_TRUE;
+        if !found {
+            return None;
+        }
+        instrument.loops.iter()
+            .take(instrument.loop_count as uint)
+            .find(|l| l.mode != ffi::SF_LOOP_NONE && l.end > l.start)
+            .map(|l| (l.start as i64, l.end as i64))
+    }
+
     /**
      * Check if the format of the SndInfo struct is valid.
      *
@@ -415,14 +510,29 @@ impl SndFile {
     /**
      * Close the SndFile object.
      *
-     * This function must be called before the exist of the program to destroy
-     * all the resources.
+     * The file is also closed when the SndFile is dropped, this function
+     * only reports the result of the closing.
      *
      * Return NoError if destruction success, an other error code otherwise.
      */
-    pub fn close(&self) -> Error {
+    pub fn close(mut self) -> Error {
+        self.close_handle()
+    }
+
+    /// Close the file and free the stream of the virtual IO, only once.
+    fn close_handle(&mut self) -> Error {
         unsafe {
-            ffi::sf_close(self.handle)
+            let err = if self.handle.is_not_null() {
+                ffi::sf_close(self.handle)
+            } else {
+                Error::NoError
+            };
+            self.handle = ptr::null_mut();
+            if self.io.is_not_null() {
+                let _: Box<Box<VirtualIo + Send>> = mem::transmute(self.io);
+                self.io = ptr::null_mut();
+            }
+            err
         }
     }
 
@@ -718,3 +828,78 @@ impl SndFile {
 
 }
 
+impl Drop for SndFile {
+    fn drop(&mut self) -> () {
+        self.close_handle();
+    }
+}
+
+/// Retrieve the stream given as user data to the virtual IO callbacks.
+unsafe fn vio_stream<'r>(user_data : *mut c_void) -> &'r mut Box<VirtualIo + Send> {
+    &mut *(user_data as *mut Box<VirtualIo + Send>)
+}
+
+extern "C" fn vio_get_filelen(user_data : *mut c_void) -> ffi::sf_count_t {
+    let io = unsafe { vio_stream(user_data) };
+    let pos = match io.tell() {
+        Ok(pos) => pos,
+        Err(_)  => return -1
+    };
+    let len = match io.seek(0, SeekStyle::SeekEnd).and_then(|_| io.tell()) {
+        Ok(len) => len as ffi::sf_count_t,
+        Err(_)  => -1
+    };
+    match io.seek(pos as i64, SeekStyle::SeekSet) {
+        Ok(_)  => len,
+        Err(_) => -1
+    }
+}
+
+extern "C" fn vio_seek(offset : ffi::sf_count_t,
+                       whence : i32,
+                       user_data : *mut c_void) -> ffi::sf_count_t {
+    let io = unsafe { vio_stream(user_data) };
+    let style = match whence {
+        ffi::SEEK_SET => SeekStyle::SeekSet,
+        ffi::SEEK_CUR => SeekStyle::SeekCur,
+        _             => SeekStyle::SeekEnd
+    };
+    match io.seek(offset, style).and_then(|_| io.tell()) {
+        Ok(pos) => pos as ffi::sf_count_t,
+        Err(_)  => -1
+    }
+}
+
+extern "C" fn vio_read(ptr : *mut c_void,
+                       count : ffi::sf_count_t,
+                       user_data : *mut c_void) -> ffi::sf_count_t {
+    let io = unsafe { vio_stream(user_data) };
+    let buf = unsafe {
+        mem::transmute::<_, &mut [u8]>(raw::Slice { data: ptr as *const u8,
+                                                    len: count as uint })
+    };
+    let mut read = 0;
+    while read < buf.len() {
+        match io.read(buf.slice_from_mut(read)) {
+            Ok(nb) => read += nb,
+            // EndOfFile or a real failure, libsndfile handle short reads
+            Err(_) => break
+        }
+    }
+    read as ffi::sf_count_t
+}
+
+extern "C" fn vio_write(_ptr : *const c_void,
+                        _count : ffi::sf_count_t,
+                        _user_data : *mut c_void) -> ffi::sf_count_t {
+    // Streams are read only
+    0
+}
+
+extern "C" fn vio_tell(user_data : *mut c_void) -> ffi::sf_count_t {
+    let io = unsafe { vio_stream(user_data) };
+    match io.tell() {
+        Ok(pos) => pos as ffi::sf_count_t,
+        Err(_)  => -1
+    }
+}
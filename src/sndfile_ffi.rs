@@ -119,8 +119,53 @@ pub const SF_FORMAT_SUBMASK : FORMAT_TYPE      = 0x0000FFFF;
 pub const SF_FORMAT_TYPEMASK : FORMAT_TYPE     = 0x0FFF0000;
 pub const SF_FORMAT_ENDMASK : FORMAT_TYPE      = 0x30000000;
 
+pub const SFC_GET_INSTRUMENT : i32 = 0x10D0;
+
+pub const SF_LOOP_NONE : i32        = 800;
+pub const SF_LOOP_FORWARD : i32     = 801;
+pub const SF_LOOP_BACKWARD : i32    = 802;
+pub const SF_LOOP_ALTERNATING : i32 = 803;
+
 pub type SNDFILE = c_void;
 
+pub type sf_count_t = i64;
+
+pub type sf_vio_get_filelen = extern "C" fn(user_data : *mut c_void) -> sf_count_t;
+pub type sf_vio_seek = extern "C" fn(offset : sf_count_t, whence : i32, user_data : *mut c_void) -> sf_count_t;
+pub type sf_vio_read = extern "C" fn(ptr : *mut c_void, count : sf_count_t, user_data : *mut c_void) -> sf_count_t;
+pub type sf_vio_write = extern "C" fn(ptr : *const c_void, count : sf_count_t, user_data : *mut c_void) -> sf_count_t;
+pub type sf_vio_tell = extern "C" fn(user_data : *mut c_void) -> sf_count_t;
+
+#[repr(C)]
+pub struct SF_VIRTUAL_IO {
+    pub get_filelen : sf_vio_get_filelen,
+    pub seek : sf_vio_seek,
+    pub read : sf_vio_read,
+    pub write : sf_vio_write,
+    pub tell : sf_vio_tell
+}
+
+#[repr(C)]
+pub struct SF_LOOP {
+    pub mode : i32,
+    pub start : u32,
+    pub end : u32,
+    pub count : u32
+}
+
+#[repr(C)]
+pub struct SF_INSTRUMENT {
+    pub gain : i32,
+    pub basenote : c_char,
+    pub detune : c_char,
+    pub velocity_lo : c_char,
+    pub velocity_hi : c_char,
+    pub key_lo : c_char,
+    pub key_hi : c_char,
+    pub loop_count : i32,
+    pub loops : [SF_LOOP, ..16]
+}
+
 #[repr(C)]
 pub struct FormatInfo {
     pub format : i32,
@@ -131,6 +176,7 @@ pub struct FormatInfo {
 extern "C" {
     pub fn sf_open(path : *mut c_char, mode : SF_MODE, info : *mut SndInfo) -> *mut SNDFILE;
     pub fn sf_open_fd(fd : i32, mode : SF_MODE, info : *mut SndInfo, close_desc : SF_BOOL) -> *mut SNDFILE;
+    pub fn sf_open_virtual(sfvirtual : *mut SF_VIRTUAL_IO, mode : SF_MODE, info : *mut SndInfo, user_data : *mut c_void) -> *mut SNDFILE;
     pub fn sf_format_check(info : *mut SndInfo) -> SF_BOOL;
 
     pub fn sf_seek(sndfile : *mut SNDFILE, frames : i64, whence : i32) -> i64;
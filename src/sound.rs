@@ -23,15 +23,33 @@
 
 use std::rc::Rc;
 use std::cell::RefCell;
+use std::cmp;
+use std::time::Duration;
+use std::io::timer::sleep;
 
 use internal::OpenAlData;
+use context::AudioContext;
+use error::Error;
 use sound_data::{mod, SoundData};
 use openal::{ffi, al};
 use states::State;
 use states::State::{Initial, Playing, Paused, Stopped};
 use audio_controller::AudioController;
+use fade;
+use fade::FadeCurve;
+use distance_model;
+use distance_model::DistanceModel;
+use effects;
+use effects::{EffectSlot, Filter};
+use mixer;
+use events;
+use events::PlaybackEvent;
 use audio_tags::{AudioTags, Tags};
 
+/// The interval between two checks of the state while waiting, in
+/// milliseconds.
+const WAIT_POLL: i64 = 10;
+
 /**
  * Play Sounds easily.
  *
@@ -52,15 +70,23 @@ use audio_tags::{AudioTags, Tags};
  *    snd.play();
  *
  *    // Wait until the sound is playing
- *    while snd.is_playing() {}
+ *    snd.wait_until_stopped();
  * }
  * ```
  */
 pub struct Sound {
+    /// The audio context owning the source
+    context: AudioContext,
     /// The internal OpenAl source identifier
     al_source: u32,
     /// The SoundData associated to the Sound.
-    sound_data: Rc<RefCell<SoundData>>
+    sound_data: Rc<RefCell<SoundData>>,
+    /// True if the events of the Sound are sent on a channel
+    watched: bool,
+    /// The effect slots connected to the auxiliary sends
+    effect_sends: Vec<(i32, EffectSlot)>,
+    /// The filter of the direct output
+    direct_filter: Option<Filter>
 }
 
 impl Sound {
@@ -73,24 +99,19 @@ impl Sound {
      * `path` - The path of the sound file to create the SoundData.
      *
      * # Return
-     * An Option with Some(Sound) if the Sound is created properly, or None if
-     * un error has occured.
+     * A Result with Ok(Sound) if the Sound is created properly, or Err(Error)
+     * if un error has occured.
      *
      * # Example
      * ```Rust
      * let snd = match Sound::new("path/to/the/sound.ogg") {
-     *     Some(snd) => snd,
-     *     None      => panic!("Cannot load the sound from a file !")
+     *     Ok(snd)  => snd,
+     *     Err(err) => panic!("Cannot load the sound from a file: {}", err)
      * };
      * ```
      */
-    pub fn new(path: &str) -> Option<Sound> {
-        check_openal_context!(None);
-
-        let s_data = match SoundData::new(path) {
-            Some(s_d) => Rc::new(RefCell::new(s_d)),
-            None      => return None
-        };
+    pub fn new(path: &str) -> Result<Sound, Error> {
+        let s_data = Rc::new(RefCell::new(try!(SoundData::new(path))));
 
         Sound::new_with_data(s_data)
     }
@@ -102,8 +123,8 @@ impl Sound {
      * `sound_data` - The sound_data to associate to the Sound.
      *
      * # Return
-     * An Option with Some(Sound) if the Sound is created properly, or None if
-     * un error has occured.
+     * A Result with Ok(Sound) if the Sound is created properly, or Err(Error)
+     * if un error has occured.
      *
      * # Example
      * ```Rust
@@ -112,17 +133,17 @@ impl Sound {
      * use std::cell::RefCell;
      *
      * let snd_data = match SoundData::new("path/to/the/sound.ogg") {
-     *     Some(snd_data) => Rc::new(RefCell::new(snd_data)),
-     *     None           => panic!("Cannot create the sound data !")
+     *     Ok(snd_data) => Rc::new(RefCell::new(snd_data)),
+     *     Err(err)     => panic!("Cannot create the sound data: {}", err)
      * };
      * let snd = match Sound::new_with_data(snd_data) {
-     *     Some(snd) => snd,
-     *     None      => panic!("Cannot create a sound using a sound data !")
+     *     Ok(snd)  => snd,
+     *     Err(err) => panic!("Cannot create a sound using a sound data: {}", err)
      * }
      * ```
      */
-    pub fn new_with_data(sound_data: Rc<RefCell<SoundData>>) -> Option<Sound> {
-        check_openal_context!(None);
+    pub fn new_with_data(sound_data: Rc<RefCell<SoundData>>) -> Result<Sound, Error> {
+        let context = try!(OpenAlData::check_al_context());
 
         let mut source_id = 0;
         // create the source
@@ -135,13 +156,17 @@ impl Sound {
 
         // Check if there is OpenAL internal error
         match al::openal_has_error() {
-            Some(err) => { println!("{}", err); return None; },
+            Some(err) => return Err(err),
             None => {}
         };
 
-        Some(Sound {
+        Ok(Sound {
+            context: context,
             al_source: source_id,
-            sound_data: sound_data
+            sound_data: sound_data,
+            watched: false,
+            effect_sends: Vec::new(),
+            direct_filter: None
         })
     }
 
@@ -221,10 +246,8 @@ impl AudioController for Sound {
 
         al::alSourcePlay(self.al_source);
 
-        match al::openal_has_error() {
-            None => {},
-            Some(err) => println!("{}", err)
-        }
+        // play() cannot report a failure, just clear the OpenAL error state
+        al::openal_has_error();
     }
 
      /**
@@ -332,7 +355,7 @@ impl AudioController for Sound {
     fn set_volume(&mut self, volume: f32) -> () {
         check_openal_context!(());
 
-        al::alSourcef(self.al_source, ffi::AL_GAIN, volume);
+        mixer::set_gain(self.al_source, volume);
     }
 
     /**
@@ -344,9 +367,7 @@ impl AudioController for Sound {
     fn get_volume(&self) -> f32 {
         check_openal_context!(0.);
 
-        let mut volume : f32 = 0.;
-        al::alGetSourcef(self.al_source, ffi::AL_GAIN, &mut volume);
-        volume
+        mixer::get_gain(self.al_source)
     }
 
     /**
@@ -687,12 +708,399 @@ impl AudioController for Sound {
         attenuation
     }
 
+    /**
+     * Set the distance model of the Sound.
+     *
+     * Needs the AL_EXT_source_distance_model extension, see
+     * `AudioController::set_distance_model`.
+     *
+     * # Argument
+     * * `model` - The formula attenuating the Sound with its distance
+     *
+     * # Return
+     * False if the distance model per source is not supported, true otherwise.
+     */
+    fn set_distance_model(&mut self, model: DistanceModel) -> bool {
+        check_openal_context!(false);
+
+        distance_model::set_source_model(self.al_source, model)
+    }
+
+    /**
+     * Get the distance model used by the Sound.
+     *
+     * # Return
+     * The model of the Sound if the sources use their own model, the global
+     * model otherwise.
+     */
+    fn get_distance_model(&self) -> DistanceModel {
+        check_openal_context!(DistanceModel::InverseClamped);
+
+        distance_model::get_source_model(self.al_source)
+    }
+
+    /**
+     * Send the Sound to an effect slot.
+     *
+     * # Arguments
+     * * `send` - The auxiliary send used, between 0 and
+     * `effects::get_max_sends()` excluded
+     * * `slot` - The slot receiving the samples
+     * * `gain` - The volume of the samples sent, between 0. and 1.
+     *
+     * # Return
+     * A Result containing Ok(()) on success, Err(Error) otherwise.
+     */
+    fn set_effect_send(&mut self, send: i32, slot: &EffectSlot, gain: f32) -> Result<(), Error> {
+        check_openal_context!();
+
+        try!(effects::connect_send(self.al_source, send, slot, gain));
+        self.effect_sends.retain(|&(s, _)| s != send);
+        self.effect_sends.push((send, slot.clone()));
+        Ok(())
+    }
+
+    /**
+     * Stop sending the Sound to the effect slot of an auxiliary send.
+     *
+     * # Argument
+     * * `send` - The auxiliary send to disconnect
+     */
+    fn remove_effect_send(&mut self, send: i32) -> () {
+        check_openal_context!(());
+
+        if self.effect_sends.iter().any(|&(s, _)| s == send) {
+            effects::disconnect_send(self.al_source, send);
+            self.effect_sends.retain(|&(s, _)| s != send);
+        }
+    }
+
+    /**
+     * Filter the direct output of the Sound.
+     *
+     * Nothing happens if the effects are not supported.
+     *
+     * # Argument
+     * * `filter` - The filter applied to the direct output
+     */
+    fn set_direct_filter(&mut self, filter: Filter) -> () {
+        check_openal_context!(());
+
+        if effects::set_direct_filter(self.al_source, Some(filter)) {
+            self.direct_filter = Some(filter);
+        }
+    }
+
+    /**
+     * Remove the filter of the direct output of the Sound.
+     */
+    fn remove_direct_filter(&mut self) -> () {
+        check_openal_context!(());
+
+        if self.direct_filter.is_some() {
+            effects::set_direct_filter(self.al_source, None);
+            self.direct_filter = None;
+        }
+    }
+
+    /**
+     * Get the filter of the direct output of the Sound.
+     *
+     * # Return
+     * The filter applied to the direct output, None if there is no filter
+     * or the effects are not supported.
+     */
+    fn get_direct_filter(&self) -> Option<Filter> {
+        self.direct_filter
+    }
+
+    /**
+     * Assign the Sound to a mixer group.
+     *
+     * The Sound is played at its volume multiplied by the volume of the
+     * group, see the `mixer` module.
+     *
+     * # Argument
+     * * `group` - The name of the group, or None to remove the Sound from its
+     * group
+     */
+    fn set_group(&mut self, group: Option<&str>) -> () {
+        check_openal_context!(());
+
+        mixer::set_source_group(self.al_source, group);
+    }
+
+    /**
+     * Get the mixer group of the Sound.
+     *
+     * # Return
+     * The name of the group of the Sound, None if it has no group.
+     */
+    fn get_group(&self) -> Option<String> {
+        mixer::get_source_group(self.al_source)
+    }
+
+    /**
+     * Set the inner angle of the cone of the Sound.
+     *
+     * Inside the cone defined by the direction of the Sound and this angle,
+     * the Sound is not attenuated by its orientation.
+     *
+     * The default inner angle is 360.
+     *
+     * # Argument
+     * * `angle` - The new inner angle in degrees, in the range [0., 360.]
+     */
+    fn set_cone_inner_angle(&mut self, angle: f32) -> () {
+        check_openal_context!(());
+
+        al::alSourcef(self.al_source, ffi::AL_CONE_INNER_ANGLE, angle);
+    }
+
+    /**
+     * Get the inner angle of the cone of the Sound.
+     *
+     * # Return
+     * The inner angle in degrees, in the range [0., 360.]
+     */
+    fn get_cone_inner_angle(&self) -> f32 {
+        check_openal_context!(360.);
+
+        let mut angle = 0.;
+        al::alGetSourcef(self.al_source, ffi::AL_CONE_INNER_ANGLE, &mut angle);
+        angle
+    }
+
+    /**
+     * Set the outer angle of the cone of the Sound.
+     *
+     * Outside the cone defined by the direction of the Sound and this angle,
+     * the volume of the Sound is multiplied by the outer volume. Between the
+     * inner and the outer angles, the volume is interpolated.
+     *
+     * The default outer angle is 360.
+     *
+     * # Argument
+     * * `angle` - The new outer angle in degrees, in the range [0., 360.]
+     */
+    fn set_cone_outer_angle(&mut self, angle: f32) -> () {
+        check_openal_context!(());
+
+        al::alSourcef(self.al_source, ffi::AL_CONE_OUTER_ANGLE, angle);
+    }
+
+    /**
+     * Get the outer angle of the cone of the Sound.
+     *
+     * # Return
+     * The outer angle in degrees, in the range [0., 360.]
+     */
+    fn get_cone_outer_angle(&self) -> f32 {
+        check_openal_context!(360.);
+
+        let mut angle = 0.;
+        al::alGetSourcef(self.al_source, ffi::AL_CONE_OUTER_ANGLE, &mut angle);
+        angle
+    }
+
+    /**
+     * Set the volume of the Sound outside its cone.
+     *
+     * The default outer volume is 0.
+     *
+     * # Argument
+     * * `volume` - The new outer volume, in the range [0., 1.]
+     */
+    fn set_cone_outer_volume(&mut self, volume: f32) -> () {
+        check_openal_context!(());
+
+        al::alSourcef(self.al_source, ffi::AL_CONE_OUTER_GAIN, volume);
+    }
+
+    /**
+     * Get the volume of the Sound outside its cone.
+     *
+     * # Return
+     * The outer volume, in the range [0., 1.]
+     */
+    fn get_cone_outer_volume(&self) -> f32 {
+        check_openal_context!(0.);
+
+        let mut volume = 0.;
+        al::alGetSourcef(self.al_source, ffi::AL_CONE_OUTER_GAIN, &mut volume);
+        volume
+    }
+
+    /**
+     * Set the velocity of the Sound.
+     *
+     * The velocity doesn't move the Sound, it is only used to compute the
+     * Doppler effect, see `listener::set_doppler_factor`.
+     *
+     * The default velocity is [0., 0., 0.].
+     *
+     * # Argument
+     * * `velocity` - A three dimensional vector of f32 containing the
+     * velocity of the Sound [x, y, z], in units per second.
+     */
+    fn set_velocity(&mut self, velocity: [f32, ..3]) -> () {
+        check_openal_context!(());
+
+        al::alSourcefv(self.al_source, ffi::AL_VELOCITY, &velocity[0]);
+    }
+
+    /**
+     * Get the velocity of the Sound.
+     *
+     * # Return
+     * A three dimensional vector of f32 containing the velocity of the
+     * Sound [x, y, z].
+     */
+    fn get_velocity(&self) -> [f32, ..3] {
+        check_openal_context!([0., ..3]);
+
+        let mut velocity : [f32, ..3] = [0., ..3];
+        al::alGetSourcefv(self.al_source, ffi::AL_VELOCITY, &mut velocity[0]);
+        velocity
+    }
+
+    /**
+     * Set the playback position of the Sound.
+     *
+     * If the Sound is not playing, the position is used the next time the
+     * Sound is played.
+     *
+     * # Argument
+     * * `offset` - The new position from the begining of the Sound,
+     * clamped to the duration of the Sound.
+     *
+     * # Example
+     * ```Rust
+     * let snd = Sound::new("path/to/the/sound.ogg").unwrap();
+     * snd.set_offset(Duration::milliseconds(500));
+     * snd.play(); // the sound start after 500 milliseconds
+     * ```
+     */
+    fn set_offset(&mut self, offset: Duration) -> () {
+        check_openal_context!(());
+
+        let (samplerate, total) = {
+            let infos = sound_data::get_sndinfo(&*self.sound_data.borrow());
+            (infos.samplerate, infos.frames)
+        };
+        // OpenAL rejects an offset outside of the buffer
+        let frames = cmp::max(offset.num_milliseconds() * samplerate as i64 / 1000, 0);
+        let frames = cmp::min(frames, cmp::max(total - 1, 0));
+        al::alSourcei(self.al_source, ffi::AL_SAMPLE_OFFSET, frames as i32);
+    }
+
+    /**
+     * Get the playback position of the Sound.
+     *
+     * # Return
+     * The time elapsed since the begining of the Sound.
+     */
+    fn get_offset(&self) -> Duration {
+        check_openal_context!(Duration::zero());
+
+        let samplerate = sound_data::get_sndinfo(&*self.sound_data.borrow()).samplerate;
+        let mut frames = 0;
+        al::alGetSourcei(self.al_source, ffi::AL_SAMPLE_OFFSET, &mut frames);
+        Duration::milliseconds(frames as i64 * 1000 / samplerate as i64)
+    }
+
+    /**
+     * Get the total duration of the Sound.
+     *
+     * # Return
+     * The duration of the SoundData associated to the Sound.
+     */
+    fn get_duration(&self) -> Duration {
+        self.sound_data.borrow().get_duration()
+    }
+
+    /**
+     * Change the volume of the Sound progressively.
+     *
+     * See `AudioController::fade_to`.
+     */
+    fn fade_to(&mut self, volume: f32, duration: Duration, curve: FadeCurve) -> Receiver<()> {
+        let current = self.get_volume();
+        fade::ramp(self.al_source, ffi::AL_GAIN, current, volume, duration, curve)
+    }
+
+    /**
+     * Change the pitch of the Sound progressively.
+     *
+     * See `AudioController::pitch_ramp`.
+     */
+    fn pitch_ramp(&mut self, pitch: f32, duration: Duration) -> Receiver<()> {
+        let current = self.get_pitch();
+        fade::ramp(self.al_source, ffi::AL_PITCH, current, pitch, duration, FadeCurve::Linear)
+    }
+
+    /**
+     * Get the events of the Sound.
+     *
+     * The Sound is checked every 10 milliseconds by a background task, a
+     * Sound stopped and played again between two checks sends no event.
+     *
+     * # Return
+     * The Receiver of the Stopped and Looped events of the Sound.
+     */
+    fn events(&mut self) -> Receiver<PlaybackEvent> {
+        let (sender, receiver) = channel();
+        events::watch(self.al_source, sender);
+        self.watched = true;
+        receiver
+    }
+
+    /**
+     * Block until the Sound is not playing anymore.
+     *
+     * The state of the Sound is checked every 10 milliseconds.
+     */
+    fn wait_until_stopped(&self) -> () {
+        while self.is_playing() {
+            sleep(Duration::milliseconds(WAIT_POLL));
+        }
+    }
+
+    /**
+     * Block until the Sound is not playing anymore, or the timeout expires.
+     *
+     * The state of the Sound is checked every 10 milliseconds.
+     *
+     * # Argument
+     * * `timeout` - The maximum duration of the wait
+     *
+     * # Return
+     * True if the Sound is not playing anymore, false if the timeout expired.
+     */
+    fn wait_timeout(&self, timeout: Duration) -> bool {
+        let mut waited = Duration::zero();
+
+        while self.is_playing() {
+            if waited >= timeout {
+                return false;
+            }
+            let delay = cmp::min(Duration::milliseconds(WAIT_POLL), timeout - waited);
+            sleep(delay);
+            waited = waited + delay;
+        }
+        true
+    }
 }
 
 #[unsafe_destructor]
 impl Drop for Sound {
     ///Destroy all the resources attached to the Sound.
     fn drop(&mut self) -> () {
+        fade::cancel(self.al_source);
+        if self.watched {
+            events::unwatch(self.al_source);
+        }
+        mixer::remove_source(self.al_source);
         unsafe {
             ffi::alDeleteSources(1, &mut self.al_source);
         }
@@ -703,17 +1111,26 @@ impl Drop for Sound {
 mod test {
     #![allow(non_snake_case)]
 
+    use std::time::Duration;
+    use std::io::timer::sleep;
+
     use sound::Sound;
     use states::State::{Playing, Paused, Stopped};
     use audio_controller::AudioController;
+    use fade::FadeCurve;
+    use events::PlaybackEvent;
+    use distance_model::DistanceModel;
+    use listener;
+    use effects;
+    use effects::{EffectSlot, Effect, Reverb, Filter};
 
     #[test]
     fn sound_create_OK() -> () {
         let snd = Sound::new("res/shot.wav");
-        println!("YOUHOU");
+
         match snd {
-            Some(_) => {},
-            None    => panic!()
+            Ok(_)  => {},
+            Err(_) => panic!()
         }
     }
 
@@ -722,14 +1139,14 @@ mod test {
         let snd = Sound::new("toto.wav");
 
         match snd {
-            Some(_) => panic!(),
-            None    => {}
+            Ok(_)  => panic!(),
+            Err(_) => {}
         }
     }
 
     #[test]
     fn sound_play_OK() -> () {
-        let mut snd = Sound::new("res/shot.wav").expect("Cannot create sound");
+        let mut snd = Sound::new("res/shot.wav").ok().expect("Cannot create sound");
 
         snd.play();
         assert_eq!(snd.get_state() as i32, Playing as i32);
@@ -738,7 +1155,7 @@ mod test {
 
     #[test]
     fn sound_pause_OK() -> () {
-        let mut snd = Sound::new("res/shot.wav").expect("Cannot create sound");
+        let mut snd = Sound::new("res/shot.wav").ok().expect("Cannot create sound");
 
         snd.play();
         snd.pause();
@@ -748,7 +1165,7 @@ mod test {
 
     #[test]
     fn sound_stop_OK() -> () {
-        let mut snd = Sound::new("res/shot.wav").expect("Cannot create sound");
+        let mut snd = Sound::new("res/shot.wav").ok().expect("Cannot create sound");
 
         snd.play();
         snd.stop();
@@ -758,7 +1175,7 @@ mod test {
 
     #[test]
     fn sound_is_playing_TRUE() -> () {
-        let mut snd = Sound::new("res/shot.wav").expect("Cannot create sound");
+        let mut snd = Sound::new("res/shot.wav").ok().expect("Cannot create sound");
 
         snd.play();
         assert_eq!(snd.is_playing(), true);
@@ -767,7 +1184,7 @@ mod test {
 
     #[test]
     fn sound_is_playing_FALSE() -> () {
-        let mut snd = Sound::new("res/shot.wav").expect("Cannot create sound");
+        let mut snd = Sound::new("res/shot.wav").ok().expect("Cannot create sound");
 
         assert_eq!(snd.is_playing(), false);
         snd.stop();
@@ -775,7 +1192,7 @@ mod test {
 
     #[test]
     fn sound_set_volume_OK() -> () {
-        let mut snd = Sound::new("res/shot.wav").expect("Cannot create sound");
+        let mut snd = Sound::new("res/shot.wav").ok().expect("Cannot create sound");
 
         snd.set_volume(0.7);
         assert_eq!(snd.get_volume(), 0.7);
@@ -785,7 +1202,7 @@ mod test {
     // #[test]
     // #[should_fail]
     // fn sound_set_volume_high_FAIL() -> () {
-    //     let mut snd = Sound::new("shot.wav").expect("Cannot create sound");
+    //     let mut snd = Sound::new("shot.wav").ok().expect("Cannot create sound");
 
     //     snd.set_volume(10.9);
     //     assert_eq!(snd.get_volume(), 10.9);
@@ -794,7 +1211,7 @@ mod test {
     #[test]
     #[should_fail]
     fn sound_set_volume_low_FAIL() -> () {
-        let mut snd = Sound::new("res/shot.wav").expect("Cannot create sound");
+        let mut snd = Sound::new("res/shot.wav").ok().expect("Cannot create sound");
 
         snd.set_volume(-1.);
         assert_eq!(snd.get_volume(), -1.);
@@ -802,7 +1219,7 @@ mod test {
 
     #[test]
     fn sound_set_min_volume_OK() -> () {
-        let mut snd = Sound::new("res/shot.wav").expect("Cannot create sound");
+        let mut snd = Sound::new("res/shot.wav").ok().expect("Cannot create sound");
 
         snd.set_min_volume(0.1);
         assert_eq!(snd.get_min_volume(), 0.1);
@@ -811,7 +1228,7 @@ mod test {
     #[test]
     #[should_fail]
     fn sound_set_min_volume_high_FAIL() -> () {
-        let mut snd = Sound::new("res/shot.wav").expect("Cannot create sound");
+        let mut snd = Sound::new("res/shot.wav").ok().expect("Cannot create sound");
 
         snd.set_min_volume(10.9);
         assert_eq!(snd.get_min_volume(), 10.9);
@@ -820,7 +1237,7 @@ mod test {
     #[test]
     #[should_fail]
     fn sound_set_min_volume_low_FAIL() -> () {
-        let mut snd = Sound::new("res/shot.wav").expect("Cannot create sound");
+        let mut snd = Sound::new("res/shot.wav").ok().expect("Cannot create sound");
 
         snd.set_min_volume(-1.);
         assert_eq!(snd.get_min_volume(), -1.);
@@ -828,7 +1245,7 @@ mod test {
 
     #[test]
     fn sound_set_max_volume_OK() -> () {
-        let mut snd = Sound::new("res/shot.wav").expect("Cannot create sound");
+        let mut snd = Sound::new("res/shot.wav").ok().expect("Cannot create sound");
 
         snd.set_max_volume(0.9);
         assert_eq!(snd.get_max_volume(), 0.9);
@@ -837,7 +1254,7 @@ mod test {
     #[test]
     #[should_fail]
     fn sound_set_max_volume_high_FAIL() -> () {
-        let mut snd = Sound::new("res/shot.wav").expect("Cannot create sound");
+        let mut snd = Sound::new("res/shot.wav").ok().expect("Cannot create sound");
 
         snd.set_max_volume(10.9);
         assert_eq!(snd.get_max_volume(), 10.9);
@@ -846,7 +1263,7 @@ mod test {
     #[test]
     #[should_fail]
     fn sound_set_max_volume_low_FAIL() -> () {
-        let mut snd = Sound::new("res/shot.wav").expect("Cannot create sound");
+        let mut snd = Sound::new("res/shot.wav").ok().expect("Cannot create sound");
 
         snd.set_max_volume(-1.);
         assert_eq!(snd.get_max_volume(), -1.);
@@ -854,7 +1271,7 @@ mod test {
 
     #[test]
     fn sound_is_looping_TRUE() -> () {
-        let mut snd = Sound::new("res/shot.wav").expect("Cannot create sound");
+        let mut snd = Sound::new("res/shot.wav").ok().expect("Cannot create sound");
 
         snd.set_looping(true);
         assert_eq!(snd.is_looping(), true);
@@ -862,7 +1279,7 @@ mod test {
 
     #[test]
     fn sound_is_looping_FALSE() -> () {
-        let mut snd = Sound::new("res/shot.wav").expect("Cannot create sound");
+        let mut snd = Sound::new("res/shot.wav").ok().expect("Cannot create sound");
 
         snd.set_looping(false);
         assert_eq!(snd.is_looping(), false);
@@ -870,7 +1287,7 @@ mod test {
 
     #[test]
     fn sound_set_pitch_OK() -> () {
-        let mut snd = Sound::new("res/shot.wav").expect("Cannot create sound");
+        let mut snd = Sound::new("res/shot.wav").ok().expect("Cannot create sound");
 
         snd.set_pitch(1.5);
         assert_eq!(snd.get_pitch(), 1.5);
@@ -879,7 +1296,7 @@ mod test {
     #[test]
     #[should_fail]
     fn sound_set_pitch_too_low_FAIL() -> () {
-        let mut snd = Sound::new("res/shot.wav").expect("Cannot create sound");
+        let mut snd = Sound::new("res/shot.wav").ok().expect("Cannot create sound");
 
         snd.set_pitch(-1.);
         assert_eq!(snd.get_pitch(), -1.);
@@ -889,7 +1306,7 @@ mod test {
     // #[test]
     // #[should_fail]
     // fn sound_set_pitch_too_high_FAIL() -> () {
-    //     let mut snd = Sound::new("shot.wav").expect("Cannot create sound");
+    //     let mut snd = Sound::new("shot.wav").ok().expect("Cannot create sound");
 
     //     snd.set_pitch(3.);
     //     assert_eq!(snd.get_pitch(), 3.);
@@ -897,7 +1314,7 @@ mod test {
 
      #[test]
     fn sound_set_relative_TRUE() -> () {
-        let mut snd = Sound::new("res/shot.wav").expect("Cannot create sound");
+        let mut snd = Sound::new("res/shot.wav").ok().expect("Cannot create sound");
 
         snd.set_relative(true);
         assert_eq!(snd.is_relative(), true);
@@ -905,7 +1322,7 @@ mod test {
 
     #[test]
     fn sound_set_relative_FALSE() -> () {
-        let mut snd = Sound::new("res/shot.wav").expect("Cannot create sound");
+        let mut snd = Sound::new("res/shot.wav").ok().expect("Cannot create sound");
 
         snd.set_relative(false);
         assert_eq!(snd.is_relative(), false);
@@ -915,7 +1332,7 @@ mod test {
 
     #[test]
     fn sound_set_position_OK() -> () {
-        let mut snd = Sound::new("res/shot.wav").expect("Cannot create sound");
+        let mut snd = Sound::new("res/shot.wav").ok().expect("Cannot create sound");
 
         snd.set_position([50f32, 150f32, 250f32]);
         let res = snd.get_position();
@@ -924,7 +1341,7 @@ mod test {
 
     #[test]
     fn sound_set_direction_OK() -> () {
-        let mut snd = Sound::new("res/shot.wav").expect("Cannot create sound");
+        let mut snd = Sound::new("res/shot.wav").ok().expect("Cannot create sound");
 
         snd.set_direction([50f32, 150f32, 250f32]);
         let res = snd.get_direction();
@@ -934,7 +1351,7 @@ mod test {
 
     #[test]
     fn sound_set_max_distance_OK() -> () {
-        let mut snd = Sound::new("res/shot.wav").expect("Cannot create sound");
+        let mut snd = Sound::new("res/shot.wav").ok().expect("Cannot create sound");
 
         snd.set_max_distance(70.);
         assert_eq!(snd.get_max_distance(), 70.);
@@ -943,7 +1360,7 @@ mod test {
     #[test]
     #[should_fail]
     fn sound_set_max_distance_FAIL() -> () {
-        let mut snd = Sound::new("res/shot.wav").expect("Cannot create sound");
+        let mut snd = Sound::new("res/shot.wav").ok().expect("Cannot create sound");
 
         snd.set_max_distance(-1.);
         assert_eq!(snd.get_max_distance(), -1.);
@@ -951,7 +1368,7 @@ mod test {
 
     #[test]
     fn sound_set_reference_distance_OK() -> () {
-        let mut snd = Sound::new("res/shot.wav").expect("Cannot create sound");
+        let mut snd = Sound::new("res/shot.wav").ok().expect("Cannot create sound");
 
         snd.set_reference_distance(70.);
         assert_eq!(snd.get_reference_distance(), 70.);
@@ -960,7 +1377,7 @@ mod test {
     #[test]
     #[should_fail]
     fn sound_set_reference_distance_FAIL() -> () {
-        let mut snd = Sound::new("res/shot.wav").expect("Cannot create sound");
+        let mut snd = Sound::new("res/shot.wav").ok().expect("Cannot create sound");
 
         snd.set_reference_distance(-1.);
         assert_eq!(snd.get_reference_distance(), -1.);
@@ -968,7 +1385,7 @@ mod test {
 
     #[test]
     fn sound_set_attenuation_OK() -> () {
-        let mut snd = Sound::new("res/shot.wav").expect("Cannot create sound");
+        let mut snd = Sound::new("res/shot.wav").ok().expect("Cannot create sound");
 
         snd.set_attenuation(0.5f32);
         assert_eq!(snd.get_attenuation(), 0.5f32);
@@ -977,9 +1394,191 @@ mod test {
     #[test]
     #[should_fail]
     fn sound_set_attenuation_FAIL() -> () {
-        let mut snd = Sound::new("res/shot.wav").expect("Cannot create sound");
+        let mut snd = Sound::new("res/shot.wav").ok().expect("Cannot create sound");
 
         snd.set_attenuation(-1.);
         assert_eq!(snd.get_attenuation(), -1.);
     }
+
+    #[test]
+    fn sound_get_duration_OK() -> () {
+        let snd = Sound::new("res/shot.wav").ok().expect("Cannot create sound");
+
+        assert_eq!(snd.get_duration(), Duration::milliseconds(511));
+    }
+
+    #[test]
+    fn sound_set_offset_OK() -> () {
+        let mut snd = Sound::new("res/shot.wav").ok().expect("Cannot create sound");
+
+        snd.play();
+        snd.pause();
+        snd.set_offset(Duration::milliseconds(500));
+        assert_eq!(snd.get_offset(), Duration::milliseconds(500));
+        snd.stop();
+    }
+
+    #[test]
+    fn sound_set_offset_past_end_OK() -> () {
+        let mut snd = Sound::new("res/shot.wav").ok().expect("Cannot create sound");
+
+        snd.play();
+        snd.pause();
+        snd.set_offset(Duration::seconds(10));
+        assert!(snd.get_offset() > Duration::milliseconds(500));
+        assert!(snd.get_offset() <= snd.get_duration());
+        snd.stop();
+    }
+
+    #[test]
+    fn sound_fade_to_OK() -> () {
+        let mut snd = Sound::new("res/shot.wav").ok().expect("Cannot create sound");
+
+        snd.set_volume(1.);
+        let done = snd.fade_to(0.2, Duration::milliseconds(50), FadeCurve::Linear);
+        assert!(done.recv_opt().is_ok());
+        assert_eq!(snd.get_volume(), 0.2);
+    }
+
+    #[test]
+    fn sound_fade_to_replaced_OK() -> () {
+        let mut snd = Sound::new("res/shot.wav").ok().expect("Cannot create sound");
+
+        let first = snd.fade_to(0., Duration::seconds(10), FadeCurve::Linear);
+        let second = snd.fade_to(0.5, Duration::zero(), FadeCurve::EqualPower);
+        assert!(second.recv_opt().is_ok());
+        assert!(first.recv_opt().is_err());
+        assert_eq!(snd.get_volume(), 0.5);
+    }
+
+    #[test]
+    fn sound_fade_to_dropped_OK() -> () {
+        let done = {
+            let mut snd = Sound::new("res/shot.wav").ok().expect("Cannot create sound");
+            snd.fade_to(0., Duration::seconds(10), FadeCurve::Linear)
+        };
+        assert!(done.recv_opt().is_err());
+    }
+
+    #[test]
+    fn sound_events_stopped_OK() -> () {
+        let mut snd = Sound::new("res/shot.wav").ok().expect("Cannot create sound");
+
+        let events = snd.events();
+        snd.play();
+        assert_eq!(events.recv(), PlaybackEvent::Stopped);
+    }
+
+    #[test]
+    fn sound_events_stopped_from_pause_OK() -> () {
+        let mut snd = Sound::new("res/shot.wav").ok().expect("Cannot create sound");
+
+        let events = snd.events();
+        snd.play();
+        snd.pause();
+        // Let the watcher see the Sound paused
+        sleep(Duration::milliseconds(50));
+        snd.stop();
+        assert_eq!(events.recv(), PlaybackEvent::Stopped);
+    }
+
+    #[test]
+    fn sound_events_looped_OK() -> () {
+        let mut snd = Sound::new("res/shot.wav").ok().expect("Cannot create sound");
+
+        let events = snd.events();
+        snd.set_looping(true);
+        snd.play();
+        assert_eq!(events.recv(), PlaybackEvent::Looped);
+        snd.stop();
+        assert_eq!(events.recv(), PlaybackEvent::Stopped);
+    }
+
+    #[test]
+    fn sound_wait_until_stopped_OK() -> () {
+        let mut snd = Sound::new("res/shot.wav").ok().expect("Cannot create sound");
+
+        snd.play();
+        snd.wait_until_stopped();
+        assert_eq!(snd.get_state() as i32, Stopped as i32);
+    }
+
+    #[test]
+    fn sound_wait_timeout_OK() -> () {
+        let mut snd = Sound::new("res/shot.wav").ok().expect("Cannot create sound");
+
+        snd.set_looping(true);
+        snd.play();
+        assert_eq!(snd.wait_timeout(Duration::milliseconds(50)), false);
+        snd.set_looping(false);
+        assert_eq!(snd.wait_timeout(Duration::seconds(5)), true);
+        snd.pause();
+        assert_eq!(snd.wait_timeout(Duration::zero()), true);
+    }
+
+    #[test]
+    fn sound_set_cone_OK() -> () {
+        let mut snd = Sound::new("res/shot.wav").ok().expect("Cannot create sound");
+
+        snd.set_cone_inner_angle(45.);
+        snd.set_cone_outer_angle(90.);
+        snd.set_cone_outer_volume(0.25);
+        assert_eq!(snd.get_cone_inner_angle(), 45.);
+        assert_eq!(snd.get_cone_outer_angle(), 90.);
+        assert_eq!(snd.get_cone_outer_volume(), 0.25);
+    }
+
+    #[test]
+    fn sound_set_velocity_OK() -> () {
+        let mut snd = Sound::new("res/shot.wav").ok().expect("Cannot create sound");
+
+        snd.set_velocity([10f32, 0f32, -5f32]);
+        let res = snd.get_velocity();
+        assert_eq!([res[0], res[1], res[2]][], [10f32, 0f32, -5f32][]);
+    }
+
+    #[test]
+    fn sound_set_distance_model_OK() -> () {
+        let mut snd = Sound::new("res/shot.wav").ok().expect("Cannot create sound");
+
+        // The only test switching the sources to their own model
+        if listener::set_per_source_distance_model(true) {
+            assert!(listener::is_per_source_distance_model());
+            assert!(snd.set_distance_model(DistanceModel::Exponent));
+            assert_eq!(snd.get_distance_model(), DistanceModel::Exponent);
+            listener::set_per_source_distance_model(false);
+            assert!(!listener::is_per_source_distance_model());
+        }
+    }
+
+    #[test]
+    fn sound_set_direct_filter_OK() -> () {
+        let mut snd = Sound::new("res/shot.wav").ok().expect("Cannot create sound");
+        let filter = Filter::HighPass { gain: 1., gain_lf: 0.5 };
+
+        snd.set_direct_filter(filter);
+        if !effects::is_supported() {
+            assert_eq!(snd.get_direct_filter(), None);
+            return;
+        }
+        assert_eq!(snd.get_direct_filter(), Some(filter));
+        // An out of range gain is rejected, the previous filter is kept
+        snd.set_direct_filter(Filter::HighPass { gain: 2., gain_lf: 0.5 });
+        assert_eq!(snd.get_direct_filter(), Some(filter));
+        snd.remove_direct_filter();
+        assert_eq!(snd.get_direct_filter(), None);
+    }
+
+    #[test]
+    fn sound_set_effect_send_OK() -> () {
+        if !effects::is_supported() {
+            return;
+        }
+        let mut snd = Sound::new("res/shot.wav").ok().expect("Cannot create sound");
+        let slot = EffectSlot::new(Effect::Reverb(Reverb::room())).ok().expect("Cannot create slot");
+
+        assert!(snd.set_effect_send(0, &slot, 0.5).is_ok());
+        assert!(snd.set_effect_send(effects::get_max_sends(), &slot, 1.).is_err());
+        snd.remove_effect_send(0);
+    }
 }
@@ -24,11 +24,17 @@
 use std::mem;
 use libc::c_void;
 use std::vec::Vec;
+use std::time::Duration;
+use std::io::MemReader;
+use std::num::Float;
 
 use openal::{ffi, al};
 use sndfile::{SndFile, SndInfo};
 use sndfile::OpenMode::Read;
 use internal::OpenAlData;
+use context::AudioContext;
+use error::Error;
+use audio_tags;
 use audio_tags::{Tags, AudioTags, get_sound_tags};
 
 /**
@@ -56,11 +62,13 @@ use audio_tags::{Tags, AudioTags, get_sound_tags};
  *   snd2.play();
  *
  *   // Wait until snd2 is playing
- *   while snd2.is_playing() {}
+ *   snd2.wait_until_stopped();
  * }
  * ```
  */
 pub struct SoundData {
+    /// The audio context owning the buffer
+    context: AudioContext,
     /// The SoundTags who contains all the information of the sound
     sound_tags: Tags,
     /// The sndfile samples information
@@ -83,19 +91,137 @@ impl SoundData {
      * * `path` - The path of the file to load
      *
      * # Return
-     * An Option with Some(SoundData) if the SoundData is create, or None if
+     * A Result with Ok(SoundData) if the SoundData is create, or Err(Error) if
      * an error has occured.
      */
-    pub fn new(path: &str) -> Option<SoundData> {
-        check_openal_context!(None);
+    pub fn new(path: &str) -> Result<SoundData, Error> {
+        let context = try!(OpenAlData::check_al_context());
 
-        let mut file;
+        let file = try!(SndFile::new(path, Read));
 
-        match SndFile::new(path, Read) {
-            Ok(file_) => file = file_,
-            Err(err) => { println!("{}", err); return None; }
-        };
+        SoundData::from_file(context, file)
+    }
+
+    /**
+     * Create a new SoundData from an encoded file loaded in memory.
+     *
+     * The bytes must contain a whole audio file in one of the formats
+     * supported by libsndfile, e.g. a resource embedded in the program or
+     * extracted from an archive.
+     *
+     * # Arguments
+     * * `bytes` - The content of the audio file
+     *
+     * # Return
+     * A Result with Ok(SoundData) if the SoundData is create, or Err(Error) if
+     * an error has occured.
+     *
+     * # Example
+     * ```Rust
+     * let snd_data = SoundData::from_bytes(include_bin!("sound.ogg")).unwrap();
+     * ```
+     */
+    pub fn from_bytes(bytes: &[u8]) -> Result<SoundData, Error> {
+        let context = try!(OpenAlData::check_al_context());
+
+        let file = try!(SndFile::new_with_reader(box MemReader::new(bytes.to_vec())));
+
+        SoundData::from_file(context, file)
+    }
+
+    /**
+     * Create a new SoundData from 16 bits samples generated in the program.
+     *
+     * The samples of each frame are interleaved, e.g. left then right for
+     * a stereo sound.
+     *
+     * # Arguments
+     * * `samples` - The interleaved samples
+     * * `channels` - The number of channels of the sound
+     * * `samplerate` - The number of frames per second
+     *
+     * # Return
+     * A Result with Ok(SoundData) if the SoundData is create,
+     * Err(Error::IncompleteFrame) if the number of samples is not a multiple
+     * of the channels, Err(Error::InvalidSamplerate) if the samplerate is not
+     * positive, or Err(Error) if another error has occured.
+     *
+     * # Example
+     * ```Rust
+     * // One second of a 440Hz square wave
+     * let samples: Vec<i16> = range(0, 44100).map(|i| {
+     *     if (i / 50) % 2 == 0 { 8000 } else { -8000 }
+     * }).collect();
+     * let snd_data = SoundData::from_samples_i16(samples.as_slice(), 1, 44100).unwrap();
+     * ```
+     */
+    pub fn from_samples_i16(samples: &[i16],
+                            channels: i32,
+                            samplerate: i32) -> Result<SoundData, Error> {
+        try!(SoundData::check_samples(samples.len(), channels, samplerate));
+        let context = try!(OpenAlData::check_al_context());
+
+        let buffer_id = try!(SoundData::upload(samples, channels, samplerate));
+        let nb_sample = samples.len() as i64;
+
+        Ok(SoundData {
+            context     : context,
+            sound_tags  : audio_tags::empty(),
+            snd_info    : SndInfo {
+                frames : nb_sample / channels as i64,
+                samplerate : samplerate,
+                channels : channels,
+                format : 0,
+                sections : 0,
+                seekable : 0
+            },
+            nb_sample   : nb_sample,
+            al_buffer   : buffer_id
+        })
+    }
+
+    /**
+     * Create a new SoundData from float samples generated in the program.
+     *
+     * The samples should be in the range [-1., 1.], they are clamped to this
+     * range and converted to 16 bits samples.
+     *
+     * # Arguments
+     * * `samples` - The interleaved samples
+     * * `channels` - The number of channels of the sound
+     * * `samplerate` - The number of frames per second
+     *
+     * # Return
+     * A Result with Ok(SoundData) if the SoundData is create, or Err(Error) if
+     * an error has occured, see from_samples_i16.
+     */
+    pub fn from_samples_f32(samples: &[f32],
+                            channels: i32,
+                            samplerate: i32) -> Result<SoundData, Error> {
+        try!(SoundData::check_samples(samples.len(), channels, samplerate));
+        let samples: Vec<i16> = samples.iter().map(|s| {
+            (s.max(-1.).min(1.) * 32767.) as i16
+        }).collect();
 
+        SoundData::from_samples_i16(samples.as_slice(), channels, samplerate)
+    }
+
+    /// Check that the samples make whole frames at a valid samplerate.
+    fn check_samples(len: uint, channels: i32, samplerate: i32) -> Result<(), Error> {
+        if al::get_channels_format(channels).is_none() {
+            return Err(Error::UnsupportedChannels(channels));
+        }
+        if len % channels as uint != 0 {
+            return Err(Error::IncompleteFrame);
+        }
+        if samplerate <= 0 {
+            return Err(Error::InvalidSamplerate(samplerate));
+        }
+        Ok(())
+    }
+
+    /// Load all the samples of an opened file in a new SoundData.
+    fn from_file(context: AudioContext, mut file: SndFile) -> Result<SoundData, Error> {
         let infos = file.get_sndinfo();
 
         let nb_sample = infos.channels as i64 * infos.frames;
@@ -103,16 +229,34 @@ impl SoundData {
         let mut samples = Vec::from_elem(nb_sample as uint, 0i16);
         file.read_i16(samples.as_mut_slice(), nb_sample as i64);
 
+        let buffer_id = match SoundData::upload(samples.as_slice(),
+                                                infos.channels,
+                                                infos.samplerate) {
+            Ok(buffer_id) => buffer_id,
+            Err(err)      => { file.close(); return Err(err); }
+        };
+
+        let sound_data = SoundData {
+            context     : context,
+            sound_tags  : get_sound_tags(&file),
+            snd_info    : infos,
+            nb_sample   : nb_sample,
+            al_buffer   : buffer_id
+        };
+        file.close();
+
+        Ok(sound_data)
+    }
+
+    /// Copy the samples in a new OpenAL buffer.
+    fn upload(samples: &[i16], channels: i32, samplerate: i32) -> Result<u32, Error> {
         let mut buffer_id = 0;
         let len = mem::size_of::<i16>() * (samples.len());
 
         // Retrieve format informations
-        let format =  match al::get_channels_format(infos.channels) {
+        let format =  match al::get_channels_format(channels) {
             Some(fmt) => fmt,
-            None => {
-                println!("Internal error : unrecognized format.");
-                return None;
-            }
+            None      => return Err(Error::UnsupportedChannels(channels))
         };
 
         al::alGenBuffers(1, &mut buffer_id);
@@ -120,22 +264,26 @@ impl SoundData {
                          format,
                          samples.as_ptr() as *mut c_void,
                          len as i32,
-                         infos.samplerate);
+                         samplerate);
 
         match al::openal_has_error() {
-            Some(err)   => { println!("{}", err); return None; },
-            None        => {}
-        };
-
-        let sound_data = SoundData {
-            sound_tags  : get_sound_tags(&file),
-            snd_info    : infos,
-            nb_sample   : nb_sample,
-            al_buffer   : buffer_id
-        };
-        file.close();
+            Some(err)   => {
+                unsafe { ffi::alDeleteBuffers(1, &mut buffer_id); }
+                Err(err)
+            },
+            None        => Ok(buffer_id)
+        }
+    }
 
-        Some(sound_data)
+    /**
+     * Get the duration of the samples.
+     *
+     * # Return
+     * The duration of the SoundData.
+     */
+    pub fn get_duration(&self) -> Duration {
+        let frames = self.nb_sample / self.snd_info.channels as i64;
+        Duration::milliseconds(frames * 1000 / self.snd_info.samplerate as i64)
     }
 }
 
@@ -187,7 +335,11 @@ mod test {
     #![allow(non_snake_case)]
 
     #[allow(unused_variables)]
+    use std::io::File;
+    use std::time::Duration;
+
     use sound_data::SoundData;
+    use error::Error;
 
     #[test]
     fn sounddata_create_OK() -> () {
@@ -202,4 +354,79 @@ mod test {
         #![allow(unused_variables)]
         let snd_data = SoundData::new("toto.wav").unwrap();
     }
+
+    #[test]
+    fn sounddata_create_IoError() -> () {
+        match SoundData::new("toto.wav") {
+            Err(Error::IoError(_)) => {},
+            _                      => panic!()
+        }
+    }
+
+    #[test]
+    fn sounddata_from_bytes_OK() -> () {
+        let bytes = File::open(&Path::new("res/shot.wav")).read_to_end().unwrap();
+
+        assert!(SoundData::from_bytes(bytes.as_slice()).is_ok());
+    }
+
+    #[test]
+    fn sounddata_from_bytes_FAIL() -> () {
+        assert!(SoundData::from_bytes(&[0u8, 1, 2, 3]).is_err());
+    }
+
+    #[test]
+    fn sounddata_from_samples_OK() -> () {
+        let samples = Vec::from_elem(44100, 0i16);
+        let snd_data = SoundData::from_samples_i16(samples.as_slice(), 2, 44100).unwrap();
+
+        assert_eq!(snd_data.get_duration(), Duration::milliseconds(500));
+    }
+
+    #[test]
+    fn sounddata_from_samples_f32_OK() -> () {
+        let samples = Vec::from_elem(4410, 0.5f32);
+
+        assert!(SoundData::from_samples_f32(samples.as_slice(), 1, 44100).is_ok());
+    }
+
+    #[test]
+    fn sounddata_from_samples_FAIL() -> () {
+        let samples = Vec::from_elem(300, 0i16);
+
+        match SoundData::from_samples_i16(samples.as_slice(), 3, 44100) {
+            Err(Error::UnsupportedChannels(3)) => {},
+            _                                  => panic!()
+        }
+    }
+
+    #[test]
+    fn sounddata_from_samples_incomplete_frame_FAIL() -> () {
+        let samples = Vec::from_elem(301, 0i16);
+        let float_samples = Vec::from_elem(301, 0f32);
+
+        match SoundData::from_samples_i16(samples.as_slice(), 2, 44100) {
+            Err(Error::IncompleteFrame) => {},
+            _                           => panic!()
+        }
+        match SoundData::from_samples_f32(float_samples.as_slice(), 2, 44100) {
+            Err(Error::IncompleteFrame) => {},
+            _                           => panic!()
+        }
+    }
+
+    #[test]
+    fn sounddata_from_samples_invalid_samplerate_FAIL() -> () {
+        let samples = Vec::from_elem(300, 0i16);
+        let float_samples = Vec::from_elem(300, 0f32);
+
+        match SoundData::from_samples_i16(samples.as_slice(), 1, 0) {
+            Err(Error::InvalidSamplerate(0)) => {},
+            _                                => panic!()
+        }
+        match SoundData::from_samples_f32(float_samples.as_slice(), 1, -44100) {
+            Err(Error::InvalidSamplerate(-44100)) => {},
+            _                                     => panic!()
+        }
+    }
 }